@@ -0,0 +1,589 @@
+use std::{cmp::Ordering, time::Instant};
+
+fn valid(temperatures: &[f32]) -> impl Iterator<Item = f32> + '_ {
+    temperatures.iter().copied().filter(|value| !value.is_nan())
+}
+
+fn mean(temperatures: &[f32]) -> Option<f32> {
+    let (sum, count) =
+        valid(temperatures).fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// Population variance across all channels with a valid temperature (not NaN) in °C².
+///
+/// Returns `None` if no channel has a valid temperature.
+pub fn variance(temperatures: &[f32]) -> Option<f32> {
+    let mean = mean(temperatures)?;
+    let (sum, count) = valid(temperatures).fold((0.0, 0), |(sum, count), value| {
+        (sum + (value - mean).powi(2), count + 1)
+    });
+    Some(sum / count as f32)
+}
+
+/// Population standard deviation across all valid channels in °C.
+///
+/// Returns `None` if no channel has a valid temperature.
+pub fn std_dev(temperatures: &[f32]) -> Option<f32> {
+    variance(temperatures).map(f32::sqrt)
+}
+
+/// Compares two temperatures, NaN (not connected or error) is ordered after all other temperatures.
+///
+/// Unlike [`f32::partial_cmp`] this is a total order usable with [`slice::sort_by`].
+pub fn cmp_nan_last(a: &f32, b: &f32) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Returns the channels with their temperatures, hottest first and NaN channels last.
+///
+/// Channels with equal temperatures keep their order.
+pub fn sorted_channels(temperatures: &[f32]) -> Vec<(u8, f32)> {
+    let mut channels = temperatures
+        .iter()
+        .enumerate()
+        .map(|(channel, temperature)| (channel as u8, *temperature))
+        .collect::<Vec<_>>();
+    channels.sort_by(|(_, a), (_, b)| match (a.is_nan(), b.is_nan()) {
+        (false, false) => cmp_nan_last(b, a),
+        _ => cmp_nan_last(a, b),
+    });
+    channels
+}
+
+/// Returns the change of each channel from `previous` to `current` in °C.
+///
+/// A channel is `None` if either reading is NaN or the channel is missing in `previous`.
+pub fn delta(current: &[f32], previous: &[f32]) -> Vec<Option<f32>> {
+    current
+        .iter()
+        .enumerate()
+        .map(|(channel, value)| {
+            previous
+                .get(channel)
+                .map(|previous| value - previous)
+                .filter(|delta| !delta.is_nan())
+        })
+        .collect()
+}
+
+/// How a NaN sample (not connected or error) is handled when averaging samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NanPolicy {
+    /// A single NaN sample makes the average of the channel NaN.
+    #[default]
+    Poison,
+    /// NaN samples are skipped, the average is NaN only if all samples of the channel are NaN.
+    Skip,
+}
+
+/// Averages the samples of each channel, every sample contains the temperatures of all channels.
+pub fn average_samples(samples: &[Vec<f32>], nan_policy: NanPolicy) -> Vec<f32> {
+    let channels = samples.iter().map(Vec::len).max().unwrap_or(0);
+    (0..channels)
+        .map(|channel| {
+            let values = samples
+                .iter()
+                .map(|sample| sample.get(channel).copied().unwrap_or(f32::NAN))
+                .collect::<Vec<_>>();
+            if nan_policy == NanPolicy::Poison && values.iter().any(|value| value.is_nan()) {
+                f32::NAN
+            } else {
+                mean(&values).unwrap_or(f32::NAN)
+            }
+        })
+        .collect()
+}
+
+/// Validity of the temperature of a single channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelValidity {
+    /// The temperature is plausible.
+    Valid,
+    /// No sensor is connected or the sensor reports an error (NaN).
+    NotConnected,
+    /// The temperature is outside of the plausible range.
+    Implausible,
+}
+
+/// Measurement range of the DS18B20 sensor in °C, readings outside indicate a fault.
+pub const DS18B20_MIN: f32 = -55.0;
+pub const DS18B20_MAX: f32 = 125.0;
+
+/// Checks each temperature against the physical range `min..=max` of the sensor, e.g.
+/// [`DS18B20_MIN`] and [`DS18B20_MAX`].
+///
+/// Faulty sensors can report values within the protocol range which are impossible for the sensor.
+/// Values at the limits of the protocol ([`crate::protocol::DEGREE_CELSIUS_MIN`] and
+/// [`crate::protocol::DEGREE_CELSIUS_MAX`]) are always implausible, these indicate a saturated register.
+/// Returns the validity per channel, see [`mark_implausible`] to also replace implausible values by NaN.
+pub fn validate_physical(temperatures: &[f32], min: f32, max: f32) -> Vec<ChannelValidity> {
+    temperatures
+        .iter()
+        .map(|value| {
+            if value.is_nan() {
+                ChannelValidity::NotConnected
+            } else if *value <= crate::protocol::DEGREE_CELSIUS_MIN
+                || *value >= crate::protocol::DEGREE_CELSIUS_MAX
+                || !(min..=max).contains(value)
+            {
+                ChannelValidity::Implausible
+            } else {
+                ChannelValidity::Valid
+            }
+        })
+        .collect()
+}
+
+/// Checks each temperature against the plausible range `min..=max` and replaces implausible values by NaN.
+///
+/// See [`validate_physical`] for the checks.
+/// Returns the validity per channel.
+pub fn mark_implausible(temperatures: &mut [f32], min: f32, max: f32) -> Vec<ChannelValidity> {
+    let validity = validate_physical(temperatures, min, max);
+    for (value, validity) in temperatures.iter_mut().zip(&validity) {
+        if *validity == ChannelValidity::Implausible {
+            *value = f32::NAN;
+        }
+    }
+    validity
+}
+
+/// Checks consecutive readings against the plausible range and a maximum change per reading.
+///
+/// Flaky gateways can corrupt a single register, which shows up as a wild jump of one channel.
+/// A temperature which changes by more than `max_delta` from the previous valid reading of the channel is
+/// implausible. If the following reading stays within `max_delta` of the rejected one, the change persisted
+/// and is accepted. A NaN reading resets the state of the channel.
+#[derive(Debug, Clone)]
+pub struct PlausibilityFilter {
+    min: f32,
+    max: f32,
+    max_delta: f32,
+    last: Vec<Option<f32>>,
+    rejected: Vec<Option<f32>>,
+}
+
+impl PlausibilityFilter {
+    /// Constructs a new filter
+    ///
+    /// * 'min' - Lowest plausible temperature in °C, see [`validate_physical`].
+    /// * 'max' - Highest plausible temperature in °C.
+    /// * 'max_delta' - Largest plausible change in °C between consecutive readings of a channel.
+    pub fn new(min: f32, max: f32, max_delta: f32) -> Self {
+        Self {
+            min,
+            max,
+            max_delta,
+            last: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    /// Checks the temperatures of the next reading and replaces implausible values by NaN.
+    ///
+    /// Returns the validity per channel.
+    pub fn update(&mut self, temperatures: &mut [f32]) -> Vec<ChannelValidity> {
+        let mut validity = mark_implausible(temperatures, self.min, self.max);
+        self.last.resize(temperatures.len(), None);
+        self.rejected.resize(temperatures.len(), None);
+        for (channel, value) in temperatures.iter_mut().enumerate() {
+            match validity[channel] {
+                ChannelValidity::NotConnected => {
+                    self.last[channel] = None;
+                    self.rejected[channel] = None;
+                }
+                ChannelValidity::Implausible => {}
+                ChannelValidity::Valid => {
+                    let jump = self.last[channel]
+                        .is_some_and(|last| (*value - last).abs() > self.max_delta);
+                    let persisted = self.rejected[channel]
+                        .is_some_and(|rejected| (*value - rejected).abs() <= self.max_delta);
+                    if jump && !persisted {
+                        self.rejected[channel] = Some(*value);
+                        validity[channel] = ChannelValidity::Implausible;
+                        *value = f32::NAN;
+                    } else {
+                        self.last[channel] = Some(*value);
+                        self.rejected[channel] = None;
+                    }
+                }
+            }
+        }
+        validity
+    }
+}
+
+/// Detects rapid temperature changes from timestamped readings.
+///
+/// The rate of change is computed per channel in °C per minute between consecutive readings.
+/// A NaN reading resets the state of the channel, so gaps do not produce spurious rates.
+#[derive(Debug, Clone)]
+pub struct RateMonitor {
+    threshold: f32,
+    last: Vec<Option<(Instant, f32)>>,
+}
+
+impl RateMonitor {
+    /// Constructs a new monitor
+    ///
+    /// * 'threshold' - Absolute rate of change in °C per minute which is considered too fast.
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            last: Vec::new(),
+        }
+    }
+
+    /// Adds the temperatures read at `timestamp` and returns the rate of change per channel in °C per minute.
+    ///
+    /// The rate is `None` for channels without a previous valid reading.
+    pub fn update(&mut self, timestamp: Instant, temperatures: &[f32]) -> Vec<Option<f32>> {
+        self.last.resize(temperatures.len(), None);
+        self.last
+            .iter_mut()
+            .zip(temperatures)
+            .map(|(last, temperature)| {
+                if temperature.is_nan() {
+                    *last = None;
+                    return None;
+                }
+                let rate = match last {
+                    Some((last_timestamp, last_temperature)) if timestamp > *last_timestamp => {
+                        let minutes = (timestamp - *last_timestamp).as_secs_f32() / 60.0;
+                        Some((temperature - *last_temperature) / minutes)
+                    }
+                    // Without elapsed time no rate can be computed, keep the previous reading
+                    Some(_) => return None,
+                    None => None,
+                };
+                *last = Some((timestamp, *temperature));
+                rate
+            })
+            .collect()
+    }
+
+    /// Returns the channels whose absolute rate of change exceeds the threshold.
+    pub fn exceeded(&self, rates: &[Option<f32>]) -> Vec<u8> {
+        rates
+            .iter()
+            .enumerate()
+            .filter(|(_, rate)| rate.is_some_and(|rate| rate.abs() > self.threshold))
+            .map(|(channel, _)| channel as u8)
+            .collect()
+    }
+}
+
+/// Coefficients of the Magnus formula over water (Sonntag 1990), valid from -45 °C to 60 °C.
+const MAGNUS_A: f32 = 17.62;
+const MAGNUS_B: f32 = 243.12;
+
+/// Computes the dew point in °C from a temperature in °C and the relative humidity in percent
+/// with the Magnus formula, e.g. with the humidity of an external sensor.
+///
+/// Returns `None` for a NaN temperature or a relative humidity outside of 0 (exclusive) to 100 %.
+pub fn dew_point(temperature: f32, relative_humidity: f32) -> Option<f32> {
+    if temperature.is_nan() || !(relative_humidity > 0.0 && relative_humidity <= 100.0) {
+        return None;
+    }
+    let gamma =
+        (relative_humidity / 100.0).ln() + MAGNUS_A * temperature / (MAGNUS_B + temperature);
+    Some(MAGNUS_B * gamma / (MAGNUS_A - gamma))
+}
+
+/// State of a channel controlled by a [`Thermostat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermostatState {
+    /// The temperature is below the setpoint.
+    Heating,
+    /// The temperature is above the setpoint.
+    Cooling,
+    /// The temperature is at the setpoint.
+    Satisfied,
+    /// No sensor is connected or the sensor reports an error (NaN).
+    Unknown,
+}
+
+/// Classifies temperatures against a setpoint with hysteresis for thermostat logic.
+///
+/// A channel starts heating below `setpoint - hysteresis` and cooling above `setpoint + hysteresis`,
+/// and keeps heating or cooling until the setpoint is reached. This avoids switching back and forth
+/// around the setpoint. A NaN reading resets the state of the channel.
+#[derive(Debug, Clone)]
+pub struct Thermostat {
+    setpoint: f32,
+    hysteresis: f32,
+    states: Vec<ThermostatState>,
+}
+
+impl Thermostat {
+    /// Constructs a new thermostat
+    ///
+    /// * 'setpoint' - Target temperature in °C.
+    /// * 'hysteresis' - Deviation from the setpoint in °C before heating or cooling starts.
+    pub fn new(setpoint: f32, hysteresis: f32) -> Self {
+        Self {
+            setpoint,
+            hysteresis: hysteresis.abs(),
+            states: Vec::new(),
+        }
+    }
+
+    /// Classifies the current temperatures and returns the state per channel.
+    pub fn update(&mut self, temperatures: &[f32]) -> Vec<ThermostatState> {
+        self.states
+            .resize(temperatures.len(), ThermostatState::Unknown);
+        for (state, temperature) in self.states.iter_mut().zip(temperatures) {
+            *state = match *state {
+                _ if temperature.is_nan() => ThermostatState::Unknown,
+                _ if *temperature < self.setpoint - self.hysteresis => ThermostatState::Heating,
+                _ if *temperature > self.setpoint + self.hysteresis => ThermostatState::Cooling,
+                ThermostatState::Heating if *temperature < self.setpoint => {
+                    ThermostatState::Heating
+                }
+                ThermostatState::Cooling if *temperature > self.setpoint => {
+                    ThermostatState::Cooling
+                }
+                _ => ThermostatState::Satisfied,
+            };
+        }
+        self.states.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn variance_test() {
+        assert_eq!(
+            variance(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]),
+            Some(4.0)
+        );
+        assert_eq!(
+            std_dev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]),
+            Some(2.0)
+        );
+
+        // NaN channels are skipped
+        assert_eq!(variance(&[1.0, f32::NAN, 3.0]), Some(1.0));
+
+        // single valid channel
+        assert_eq!(variance(&[f32::NAN, 21.5, f32::NAN]), Some(0.0));
+        assert_eq!(std_dev(&[f32::NAN, 21.5, f32::NAN]), Some(0.0));
+
+        // no valid channel
+        assert_eq!(variance(&[f32::NAN; 8]), None);
+        assert_eq!(std_dev(&[]), None);
+    }
+
+    #[test]
+    fn sorted_channels_test() {
+        assert_eq!(cmp_nan_last(&1.0, &2.0), Ordering::Less);
+        assert_eq!(cmp_nan_last(&f32::NAN, &2.0), Ordering::Greater);
+        assert_eq!(cmp_nan_last(&-2.0, &f32::NAN), Ordering::Less);
+        assert_eq!(cmp_nan_last(&f32::NAN, &f32::NAN), Ordering::Equal);
+
+        let sorted = sorted_channels(&[20.0, f32::NAN, 25.5, -3.0, f32::NAN, 20.0]);
+        let channels = sorted
+            .iter()
+            .map(|(channel, _)| *channel)
+            .collect::<Vec<_>>();
+        assert_eq!(channels, vec![2, 0, 5, 3, 1, 4]);
+        assert_eq!(sorted[0].1, 25.5);
+        assert!(sorted[5].1.is_nan());
+        assert!(sorted_channels(&[]).is_empty());
+    }
+
+    #[test]
+    fn average_samples_test() {
+        let samples = vec![vec![20.0, f32::NAN, 1.0], vec![21.0, 5.0, 2.0]];
+        crate::assert_temperatures_eq!(
+            average_samples(&samples, NanPolicy::Poison),
+            [20.5, f32::NAN, 1.5],
+            0.0
+        );
+        crate::assert_temperatures_eq!(
+            average_samples(&samples, NanPolicy::Skip),
+            [20.5, 5.0, 1.5],
+            0.0
+        );
+        crate::assert_temperatures_eq!(
+            average_samples(&[vec![f32::NAN], vec![f32::NAN]], NanPolicy::Skip),
+            [f32::NAN],
+            0.0
+        );
+        assert!(average_samples(&[], NanPolicy::Skip).is_empty());
+    }
+
+    #[test]
+    fn delta_test() {
+        assert_eq!(
+            delta(&[21.5, f32::NAN, 20.0, 19.0], &[21.0, 20.0, f32::NAN]),
+            [Some(0.5), None, None, None]
+        );
+        assert!(delta(&[], &[21.0]).is_empty());
+    }
+
+    #[test]
+    fn mark_implausible_test() {
+        let mut temperatures = [21.5, f32::NAN, -60.0, 130.0, 3276.7, -3276.7, -55.0, 125.0];
+        let validity = mark_implausible(&mut temperatures, -55.0, 125.0);
+        assert_eq!(
+            validity,
+            [
+                ChannelValidity::Valid,
+                ChannelValidity::NotConnected,
+                ChannelValidity::Implausible,
+                ChannelValidity::Implausible,
+                ChannelValidity::Implausible,
+                ChannelValidity::Implausible,
+                ChannelValidity::Valid,
+                ChannelValidity::Valid,
+            ]
+        );
+        assert_eq!(temperatures[0], 21.5);
+        assert!(temperatures[1..6].iter().all(|value| value.is_nan()));
+        assert_eq!(temperatures[6], -55.0);
+        assert_eq!(temperatures[7], 125.0);
+
+        // saturated registers are implausible even within the plausible range
+        let mut temperatures = [3276.7];
+        assert_eq!(
+            mark_implausible(&mut temperatures, f32::MIN, f32::MAX),
+            [ChannelValidity::Implausible]
+        );
+    }
+
+    #[test]
+    fn plausibility_filter() {
+        let mut filter = PlausibilityFilter::new(DS18B20_MIN, DS18B20_MAX, 5.0);
+        let mut temperatures = [21.5, 20.0, 130.0];
+        assert_eq!(
+            filter.update(&mut temperatures),
+            [
+                ChannelValidity::Valid,
+                ChannelValidity::Valid,
+                ChannelValidity::Implausible
+            ]
+        );
+
+        // a wild jump of channel 0 is rejected, channel 1 changes within the limit
+        let mut temperatures = [85.0, 24.5, 21.0];
+        assert_eq!(
+            filter.update(&mut temperatures),
+            [
+                ChannelValidity::Implausible,
+                ChannelValidity::Valid,
+                ChannelValidity::Valid
+            ]
+        );
+        assert!(temperatures[0].is_nan());
+        assert_eq!(temperatures[1..], [24.5, 21.0]);
+
+        // the spike is gone, the next reading is compared with the last valid one
+        let mut temperatures = [22.0, 24.0, 21.5];
+        assert!(filter
+            .update(&mut temperatures)
+            .iter()
+            .all(|validity| *validity == ChannelValidity::Valid));
+
+        // a step which persists is accepted with the second reading
+        let mut temperatures = [40.0, f32::NAN, 21.5];
+        assert_eq!(
+            filter.update(&mut temperatures)[..2],
+            [ChannelValidity::Implausible, ChannelValidity::NotConnected]
+        );
+        let mut temperatures = [41.0, 60.0, 21.5];
+        assert_eq!(
+            filter.update(&mut temperatures)[..2],
+            [ChannelValidity::Valid, ChannelValidity::Valid]
+        );
+        assert_eq!(temperatures[..2], [41.0, 60.0]);
+    }
+
+    #[test]
+    fn validate_physical_test() {
+        let temperatures = [21.5, f32::NAN, -55.1, 125.1, -55.0, 125.0];
+        assert_eq!(
+            validate_physical(&temperatures, DS18B20_MIN, DS18B20_MAX),
+            [
+                ChannelValidity::Valid,
+                ChannelValidity::NotConnected,
+                ChannelValidity::Implausible,
+                ChannelValidity::Implausible,
+                ChannelValidity::Valid,
+                ChannelValidity::Valid,
+            ]
+        );
+        // the temperatures are not modified
+        assert_eq!(temperatures[2], -55.1);
+    }
+
+    #[test]
+    fn rate_monitor_test() {
+        let mut monitor = RateMonitor::new(1.0);
+        let start = Instant::now();
+
+        assert_eq!(
+            monitor.update(start, &[20.0, 20.0, 20.0]),
+            [None, None, None]
+        );
+
+        let rates = monitor.update(start + Duration::from_secs(30), &[20.25, 21.0, f32::NAN]);
+        assert_eq!(rates, [Some(0.5), Some(2.0), None]);
+        assert_eq!(monitor.exceeded(&rates), [1]);
+
+        // the NaN reading reset channel 2, no rate against the reading before the gap
+        let rates = monitor.update(start + Duration::from_secs(90), &[19.75, 21.0, 30.0]);
+        assert_eq!(rates, [Some(-0.5), Some(0.0), None]);
+        assert!(monitor.exceeded(&rates).is_empty());
+
+        let rates = monitor.update(start + Duration::from_secs(150), &[17.75, 21.0, 30.0]);
+        assert_eq!(rates, [Some(-2.0), Some(0.0), Some(0.0)]);
+        assert_eq!(monitor.exceeded(&rates), [0]);
+
+        // no elapsed time
+        let rates = monitor.update(start + Duration::from_secs(150), &[18.0, 21.0, 30.0]);
+        assert_eq!(rates, [None, None, None]);
+    }
+
+    #[test]
+    fn thermostat_test() {
+        use ThermostatState::*;
+        let mut thermostat = Thermostat::new(20.0, 0.5);
+        assert_eq!(
+            thermostat.update(&[19.0, 20.2, 21.0, f32::NAN]),
+            [Heating, Satisfied, Cooling, Unknown]
+        );
+        // within the hysteresis the channels keep heating or cooling until the setpoint is reached
+        assert_eq!(
+            thermostat.update(&[19.8, 19.8, 20.2, 19.0]),
+            [Heating, Satisfied, Cooling, Heating]
+        );
+        assert_eq!(
+            thermostat.update(&[20.0, 19.6, 19.9, f32::NAN]),
+            [Satisfied, Satisfied, Satisfied, Unknown]
+        );
+        assert_eq!(thermostat.update(&[19.8]), [Satisfied]);
+    }
+
+    #[test]
+    fn dew_point_test() {
+        let value = dew_point(20.0, 50.0).unwrap();
+        assert!((value - 9.26).abs() < 0.01, "{value}");
+        let value = dew_point(21.9, 100.0).unwrap();
+        assert!((value - 21.9).abs() < 0.001, "{value}");
+        assert_eq!(dew_point(f32::NAN, 50.0), None);
+        assert_eq!(dew_point(20.0, 0.0), None);
+        assert_eq!(dew_point(20.0, 100.1), None);
+        assert_eq!(dew_point(20.0, f32::NAN), None);
+    }
+}
@@ -0,0 +1,24 @@
+/// Logs if a client is dropped without calling its `close`, with the `log` feature.
+///
+/// Dropping a client releases the connection as well, but errors of closing it are not reported.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "log"), allow(dead_code))]
+pub(crate) struct CloseGuard {
+    closed: bool,
+}
+
+impl CloseGuard {
+    /// Marks the client as closed explicitly.
+    pub(crate) fn close(&mut self) {
+        self.closed = true;
+    }
+}
+
+#[cfg(feature = "log")]
+impl Drop for CloseGuard {
+    fn drop(&mut self) {
+        if !self.closed {
+            log::debug!("R4DCB08 client dropped without close, the connection is released without reporting errors");
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::tokio_sync_client::R4DCB08;
+use std::{fmt, time::Duration};
+
+type Result<T> = std::result::Result<T, crate::tokio_error::Error>;
+
+/// Describes how to connect to a R4DCB08.
+///
+/// The description can be kept to reconnect later and is displayed in a human readable form for logging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionSpec {
+    /// Modbus/TCP, e.g. through a RS485 to TCP gateway.
+    #[cfg(feature = "tokio-tcp-sync")]
+    Tcp { addr: std::net::SocketAddr },
+    /// Modbus/RTU over a serial port.
+    ///
+    /// * 'exclusive' - Open the serial port with exclusive access, see [`crate::tokio_serial::serial_port_builder`].
+    #[cfg(feature = "tokio-rtu-sync")]
+    Rtu {
+        device: String,
+        baud_rate: u32,
+        address: u8,
+        exclusive: bool,
+    },
+}
+
+impl ConnectionSpec {
+    /// Connects to the device and sets the timeout of the returned client.
+    pub fn connect(&self, timeout: Duration) -> Result<R4DCB08> {
+        match self {
+            #[cfg(feature = "tokio-tcp-sync")]
+            ConnectionSpec::Tcp { addr } => connect_tcp_with_timeout(*addr, timeout),
+            #[cfg(feature = "tokio-rtu-sync")]
+            ConnectionSpec::Rtu {
+                device,
+                baud_rate,
+                address,
+                exclusive,
+            } => {
+                let mut d = R4DCB08::new(
+                    tokio_modbus::client::sync::rtu::connect_slave_with_timeout(
+                        &crate::tokio_serial::serial_port_builder(device, *baud_rate, *exclusive),
+                        tokio_modbus::Slave(*address),
+                        Some(timeout),
+                    )
+                    .map_err(|error| crate::tokio_serial::open_error(device, error))?,
+                );
+                d.set_transport(crate::protocol::Framing::Rtu);
+                d.set_slave_address(*address);
+                Ok(d)
+            }
+        }
+    }
+
+    /// Returns the connection using `baud_rate`, Modbus/TCP connections are returned unchanged.
+    #[cfg_attr(not(feature = "tokio-rtu-sync"), allow(unused_variables))]
+    pub fn with_baud_rate(&self, baud_rate: crate::protocol::BaudRate) -> ConnectionSpec {
+        let mut spec = self.clone();
+        match &mut spec {
+            #[cfg(feature = "tokio-tcp-sync")]
+            ConnectionSpec::Tcp { .. } => {}
+            #[cfg(feature = "tokio-rtu-sync")]
+            ConnectionSpec::Rtu {
+                baud_rate: bits_per_second,
+                ..
+            } => *bits_per_second = baud_rate.bits_per_second(),
+        }
+        spec
+    }
+
+    /// Writes the new baud rate to the device, which takes effect after the device is powered up again.
+    ///
+    /// The returned [`PendingBaudChange`] reconnects at the new baud rate once the device was power-cycled.
+    /// Modbus/TCP connections are reconnected unchanged, the gateway must be reconfigured separately.
+    pub fn change_baud_rate(
+        &self,
+        d: &mut R4DCB08,
+        new: crate::protocol::BaudRate,
+    ) -> Result<PendingBaudChange> {
+        let old = d.read_baud_rate()?;
+        d.set_baud_rate(new)?;
+        Ok(PendingBaudChange {
+            old,
+            new,
+            spec: self.with_baud_rate(new),
+        })
+    }
+}
+
+/// A baud rate written to the device, which is used after the device is powered up again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingBaudChange {
+    pub old: crate::protocol::BaudRate,
+    pub new: crate::protocol::BaudRate,
+    spec: ConnectionSpec,
+}
+
+impl PendingBaudChange {
+    /// Returns the connection using the new baud rate.
+    pub fn spec(&self) -> &ConnectionSpec {
+        &self.spec
+    }
+
+    /// Connects using the new baud rate, the device must have been power-cycled before.
+    pub fn reconnect(&self, timeout: Duration) -> Result<R4DCB08> {
+        self.spec.connect(timeout)
+    }
+}
+
+/// Connects to a R4DCB08 using Modbus/TCP and fails if the connection is not established within `connect_timeout`.
+///
+/// Without a timeout connecting to an unreachable host blocks until the timeout of the operating system,
+/// which can exceed a minute. The timeout is also used as the timeout of the returned client.
+#[cfg(feature = "tokio-tcp-sync")]
+pub fn connect_tcp_with_timeout(
+    addr: std::net::SocketAddr,
+    connect_timeout: Duration,
+) -> Result<R4DCB08> {
+    let ctx = tokio_modbus::client::sync::tcp::connect_with_timeout(addr, Some(connect_timeout))
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::TimedOut => {
+                crate::tokio_error::Error::TcpConnectTimeout(addr, connect_timeout)
+            }
+            _ => tokio_modbus::Error::Transport(error).into(),
+        })?;
+    let mut d = R4DCB08::new(ctx);
+    d.set_transport(crate::protocol::Framing::Tcp);
+    d.set_slave_address(tokio_modbus::Slave::tcp_device().0);
+    Ok(d)
+}
+
+impl fmt::Display for ConnectionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "tokio-tcp-sync")]
+            ConnectionSpec::Tcp { addr } => write!(f, "TCP {}", addr),
+            #[cfg(feature = "tokio-rtu-sync")]
+            ConnectionSpec::Rtu {
+                device,
+                baud_rate,
+                address,
+                ..
+            } => write!(
+                f,
+                "RTU {} address {:#04x} baud rate {}",
+                device, address, baud_rate
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        #[cfg(feature = "tokio-tcp-sync")]
+        assert_eq!(
+            ConnectionSpec::Tcp {
+                addr: "192.168.0.222:502".parse().unwrap()
+            }
+            .to_string(),
+            "TCP 192.168.0.222:502"
+        );
+        #[cfg(feature = "tokio-rtu-sync")]
+        assert_eq!(
+            ConnectionSpec::Rtu {
+                device: "/dev/ttyUSB0".to_string(),
+                baud_rate: 9600,
+                address: 1,
+                exclusive: true,
+            }
+            .to_string(),
+            "RTU /dev/ttyUSB0 address 0x01 baud rate 9600"
+        );
+    }
+
+    #[test]
+    fn with_baud_rate() {
+        #[cfg(feature = "tokio-tcp-sync")]
+        {
+            let spec = ConnectionSpec::Tcp {
+                addr: "192.168.0.222:502".parse().unwrap(),
+            };
+            assert_eq!(spec.with_baud_rate(crate::protocol::BaudRate::B2400), spec);
+        }
+        #[cfg(feature = "tokio-rtu-sync")]
+        assert_eq!(
+            ConnectionSpec::Rtu {
+                device: "/dev/ttyUSB0".to_string(),
+                baud_rate: 9600,
+                address: 1,
+                exclusive: true,
+            }
+            .with_baud_rate(crate::protocol::BaudRate::B2400)
+            .to_string(),
+            "RTU /dev/ttyUSB0 address 0x01 baud rate 2400"
+        );
+    }
+}
@@ -8,12 +8,29 @@ pub enum Error {
         max = protocol::CHANNELS_MAX
     )]
     ChannelOutOfRange(u8),
+    #[error(
+        "The channel range starting at {0} with {1} channels is outside the permissible range of {min} to {max}",
+        min = protocol::CHANNELS_MIN,
+        max = protocol::CHANNELS_MAX
+    )]
+    ChannelRangeOutOfRange(u8, u8),
+    #[error(
+        "{0} channels exceed the {max} channels of the device",
+        max = protocol::NUMBER_OF_CHANNELS
+    )]
+    TooManyChannels(usize),
     #[error(
         "The degree celsius value {0} is outside the permissible range of {min} to {max}",
         min = protocol::DEGREE_CELSIUS_MIN,
         max = protocol::DEGREE_CELSIUS_MAX
     )]
     DegreeCelsiusOutOfRange(f32),
+    #[error(
+        "The temperature correction {0} is outside the conservative range of {min} to {max}",
+        min = protocol::CORRECTION_MIN,
+        max = protocol::CORRECTION_MAX
+    )]
+    CorrectionOutOfRange(f32),
     #[error(
         "The duration value {0} is outside the permissible range of {min} to {max}",
         min = protocol::DURATION_MIN,
@@ -26,4 +43,32 @@ pub enum Error {
         max = protocol::ADDRESS_MAX
     )]
     AddressOutOfRange(u8),
+    #[error("The broadcast address {0:#04x} cannot be assigned to a device")]
+    InvalidAddressAssignment(u8),
+    #[error("The channel {0} reports no connected sensor")]
+    ChannelDisconnected(u8),
+    #[error("Expected {expected} registers, but got {actual}")]
+    RegisterCountMismatch { expected: u16, actual: usize },
+    #[error("The register value {0:#06x} is not a valid baud rate")]
+    InvalidBaudRate(u16),
+    #[error("The register value {0:#06x} is not a valid address")]
+    InvalidAddress(u16),
+    #[error("The device reports the broadcast address {0:#04x} as its address, a factory reset is recommended")]
+    BroadcastAddressReported(u8),
+    #[error("The frame is not a valid automatic report frame")]
+    InvalidFrame,
+    #[error("CRC mismatch, expected {expected:#06x} but got {actual:#06x}")]
+    CrcMismatch { expected: u16, actual: u16 },
+    #[error("The frame is not a valid Modbus ASCII frame")]
+    InvalidAsciiFrame,
+    #[error("LRC mismatch, expected {expected:#04x} but got {actual:#04x}")]
+    LrcMismatch { expected: u8, actual: u8 },
+    #[error("Unexpected response to function code {0:#04x}")]
+    UnexpectedResponse(u8),
+    #[error("Cannot parse channel {0:?}")]
+    InvalidChannel(String),
+    #[error("The rate limit {0} is not a positive number of requests per second")]
+    InvalidRateLimit(f64),
+    #[error("Invalid register layout: {0}")]
+    InvalidRegisterLayout(String),
 }
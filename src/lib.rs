@@ -1,8 +1,12 @@
 mod error;
 
 pub use error::Error;
+pub mod analysis;
 pub mod protocol;
 
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
 #[cfg(any(
     feature = "tokio-rtu-sync",
     feature = "tokio-tcp-sync",
@@ -11,11 +15,39 @@ pub mod protocol;
 ))]
 pub mod tokio_error;
 
+#[cfg(any(
+    feature = "tokio-rtu-sync",
+    feature = "tokio-tcp-sync",
+    feature = "tokio-rtu",
+    feature = "tokio-tcp"
+))]
+pub mod retry;
+
+#[cfg(any(
+    feature = "tokio-rtu-sync",
+    feature = "tokio-tcp-sync",
+    feature = "tokio-rtu",
+    feature = "tokio-tcp"
+))]
+mod close_guard;
+
 #[cfg(any(feature = "tokio-rtu-sync", feature = "tokio-tcp-sync"))]
 pub mod tokio_sync_client;
 
+#[cfg(any(feature = "tokio-rtu-sync", feature = "tokio-tcp-sync"))]
+pub mod connection;
+
 #[cfg(any(feature = "tokio-rtu", feature = "tokio-tcp"))]
 pub mod tokio_async_client;
 
 #[cfg(any(feature = "tokio-rtu", feature = "tokio-rtu-sync"))]
 pub mod tokio_serial;
+
+#[cfg(any(feature = "tokio-ascii", feature = "tokio-hybrid"))]
+mod tokio_pdu;
+
+#[cfg(feature = "tokio-ascii")]
+pub mod tokio_ascii;
+
+#[cfg(feature = "tokio-hybrid")]
+pub mod tokio_hybrid;
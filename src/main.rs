@@ -5,9 +5,17 @@ use dialoguer::Confirm;
 use flexi_logger::{Logger, LoggerHandle};
 use log::*;
 use paho_mqtt as mqtt;
-use r4dcb08_lib::{protocol as proto, tokio_sync_client::R4DCB08};
-use std::io::{stdout, Write};
-use std::{fmt, ops::Deref, panic, time::Duration};
+use r4dcb08_lib::{connection::ConnectionSpec, protocol as proto, tokio_sync_client::R4DCB08};
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::{
+    collections::VecDeque,
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    ops::{Deref, RangeInclusive},
+    panic,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct BaudRate(proto::BaudRate);
@@ -19,15 +27,8 @@ impl fmt::Display for BaudRate {
 }
 
 impl BaudRate {
-    pub fn iter() -> core::array::IntoIter<Self, 5> {
-        [
-            Self(proto::BaudRate::B1200),
-            Self(proto::BaudRate::B2400),
-            Self(proto::BaudRate::B4800),
-            Self(proto::BaudRate::B9600),
-            Self(proto::BaudRate::B19200),
-        ]
-        .into_iter()
+    pub fn iter() -> impl Iterator<Item = Self> {
+        proto::BaudRate::ALL.into_iter().map(Self)
     }
 
     pub fn from(baud_rate: proto::BaudRate) -> Self {
@@ -46,13 +47,7 @@ impl BaudRate {
     }
 
     pub fn as_u16(&self) -> u16 {
-        match self.0 {
-            proto::BaudRate::B1200 => 1200,
-            proto::BaudRate::B2400 => 2400,
-            proto::BaudRate::B4800 => 4800,
-            proto::BaudRate::B9600 => 9600,
-            proto::BaudRate::B19200 => 19200,
-        }
+        self.bits_per_second() as u16
     }
 
     pub fn minimum_rtu_delay(&self) -> Duration {
@@ -98,6 +93,19 @@ fn parse_address(s: &str) -> Result<u8, String> {
     clap_num::maybe_hex_range(s, proto::ADDRESS_MIN, proto::ADDRESS_MAX)
 }
 
+/// Parses an inclusive range of RS485 addresses like "1-8".
+fn parse_address_range(s: &str) -> Result<RangeInclusive<u8>, String> {
+    let (start, end) = s
+        .split_once('-')
+        .ok_or_else(|| format!("expected a range like 1-8, got {s:?}"))?;
+    let start = parse_address(start.trim())?;
+    let end = parse_address(end.trim())?;
+    if start > end {
+        return Err(format!("start {start} is greater than end {end}"));
+    }
+    Ok(start..=end)
+}
+
 fn parse_baud_rate(s: &str) -> Result<BaudRate, String> {
     let val = s.parse::<u16>().map_err(|e| format!("{e}"))?;
     let val = BaudRate::from_u16(val).map_err(|e| format!("{e}"))?;
@@ -143,6 +151,10 @@ enum CliConnection {
         #[arg(short, long, default_value_t = proto::FACTORY_DEFAULT_ADDRESS, value_parser = parse_address)]
         address: u8,
 
+        /// Open the serial port with exclusive access (Unix only)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        exclusive: bool,
+
         #[command(subcommand)]
         command: CliCommands,
     },
@@ -151,7 +163,17 @@ enum CliConnection {
         /// Device
         #[arg(short, long, default_value_t = default_device_name())]
         device: String,
+
+        /// Open the serial port with exclusive access (Unix only)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        exclusive: bool,
+
+        /// Output format of the result
+        #[arg(long, value_enum, default_value = "text")]
+        format: ScanFormat,
     },
+    /// List the available serial ports
+    ListPorts,
 }
 
 #[derive(Subcommand, Debug, Clone, PartialEq, Default)]
@@ -159,6 +181,19 @@ enum DaemonMode {
     #[default]
     /// Print values to stdout [default]
     Stdout,
+    /// Print values as SenML JSON (RFC 8428) to stdout, channels without a temperature are omitted
+    Senml,
+    /// Run a shell command on each reading, the temperatures are passed as environment variables
+    /// TEMPERATURE_0 to TEMPERATURE_7 ("NaN" if a channel has no sensor), CHANNELS, the comma separated changed channels,
+    /// and STALE, "1" for the reading loaded from the state file at startup
+    Exec {
+        /// Command executed by the shell, e.g. 'echo $TEMPERATURE_0 >> log.txt'
+        command: String,
+
+        /// The command is killed if it runs longer, so it does not stall polling
+        #[arg(value_parser = humantime::parse_duration, long, default_value = "5sec")]
+        timeout: Duration,
+    },
     /// Send values to a MQTT Broker
     Mqtt {
         /// URL to the MQTT broker like: mqtt://localhost:1883
@@ -179,7 +214,49 @@ enum DaemonMode {
         /// Quality of service to use
         #[arg(long, default_value = "0")]
         qos: u8,
+
+        /// Publish one topic per channel or all channels as a retained JSON object to <TOPIC>/state
+        #[arg(long, value_enum, default_value = "per-channel")]
+        format: MqttFormat,
     },
+    /// Send each reading as JSON datagram to a UDP collector, send errors are only logged
+    Udp {
+        /// Address of the collector like: collector.local:9999
+        target: String,
+
+        /// Device identifier in the datagram, defaults to the connection, e.g. "RTU /dev/ttyUSB0 address 0x01 baud rate 9600"
+        #[arg(long)]
+        device_id: Option<String>,
+    },
+}
+
+/// Output format of the RTU scan.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum ScanFormat {
+    /// Progress and result as text
+    Text,
+    /// Only the result as JSON object, e.g. {"found":true,"address":"0x01","baud_rate":9600}
+    Json,
+}
+
+/// Action of the daemon watchdog detecting a wedged device.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum WatchdogAction {
+    /// Log a warning and continue polling
+    Log,
+    /// Exit with an error
+    Exit,
+    /// Reopen the connection to the device
+    Reconnect,
+}
+
+/// Payload format of the MQTT daemon mode.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum MqttFormat {
+    /// The temperature of each channel to <TOPIC>/<CHANNEL>
+    PerChannel,
+    /// A JSON object with all channels to <TOPIC>/state, e.g. for a Home Assistant JSON attributes sensor
+    Json,
 }
 
 #[derive(Subcommand, Debug, Clone, PartialEq)]
@@ -190,12 +267,99 @@ enum CliCommands {
         #[arg(value_parser = humantime::parse_duration, short, long, default_value = "2sec")]
         poll_iterval: Duration,
 
+        /// Read only this number of channels per poll, round-robin, to spread the bus load
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=proto::NUMBER_OF_CHANNELS as i64))]
+        stagger: Option<u8>,
+
+        /// Number of consecutive failed polls which are logged and skipped before the watchdog fires
+        #[arg(long, default_value_t = 0)]
+        max_consecutive_failures: u32,
+
+        /// Fire the watchdog if the readings of all channels stay exactly the same for this number of polls
+        #[arg(long, value_parser = clap::value_parser!(u32).range(2..))]
+        stuck_polls: Option<u32>,
+
+        /// Action of the watchdog on too many consecutive failures or stuck readings
+        #[arg(long, value_enum, default_value = "exit")]
+        watchdog_action: WatchdogAction,
+
+        /// Software offsets in °C per channel added to the temperatures before the output, e.g. 0.5,0,-1.2
+        #[arg(long, value_delimiter = ',', allow_hyphen_values = true, num_args = 1, value_parser = parse_degree_celsius)]
+        offsets: Vec<f32>,
+
+        /// Publish a channel only if its temperature changed by more than this deadband in °C
+        #[arg(long, value_name = "DEADBAND")]
+        on_change: Option<f32>,
+
+        /// Publish a channel at least this often, even if its temperature did not change
+        #[arg(value_parser = humantime::parse_duration, long, default_value = "60sec", requires = "on_change")]
+        heartbeat: Duration,
+
+        /// Write the last reading to this file on each poll, it is published as stale reading at startup
+        #[arg(long)]
+        state_file: Option<PathBuf>,
+
+        /// Number of attempts to open the connection at startup, 0 retries forever, e.g. to wait for the device at boot
+        #[arg(long, default_value_t = 1)]
+        startup_attempts: u32,
+
+        /// Wait before the first retry to open the connection, doubled for each further retry up to 1 minute
+        #[arg(value_parser = humantime::parse_duration, long, default_value = "1sec")]
+        startup_retry_interval: Duration,
+
         #[command(subcommand)]
         mode: DaemonMode,
     },
 
+    /// Continuously poll the device and print the bus health over a sliding window
+    Health {
+        /// Sliding window for the statistics
+        #[arg(value_parser = humantime::parse_duration, short, long, default_value = "60sec")]
+        window: Duration,
+
+        /// Interval for repeated polling
+        #[arg(value_parser = humantime::parse_duration, short, long, default_value = "2sec")]
+        poll_interval: Duration,
+    },
+
     /// Read the current temperature from all channels
-    Read,
+    Read {
+        /// Print the register values in hex as received as well
+        #[arg(long)]
+        raw: bool,
+
+        /// Print the temperatures as bar chart
+        #[arg(long, conflicts_with = "raw")]
+        chart: bool,
+
+        /// Temperature in °C of an empty bar
+        #[arg(
+            long,
+            default_value_t = 0.0,
+            allow_hyphen_values = true,
+            requires = "chart"
+        )]
+        chart_min: f32,
+
+        /// Temperature in °C of a full bar
+        #[arg(
+            long,
+            default_value_t = 50.0,
+            allow_hyphen_values = true,
+            requires = "chart"
+        )]
+        chart_max: f32,
+    },
+
+    /// Read the current temperature from contiguous channels
+    ReadRange {
+        /// First temperature sensor channel 0 to 7
+        #[arg(value_parser = parse_channel)]
+        start: u8,
+        /// Number of channels to read
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=proto::NUMBER_OF_CHANNELS as i64))]
+        count: u8,
+    },
 
     /// Read the current temperature correction values form all channels
     ReadCorrection,
@@ -207,7 +371,11 @@ enum CliCommands {
     ReadAutomaticReport,
 
     /// Read all values
-    ReadAll,
+    ReadAll {
+        /// Show a leading "+" for positive temperatures and corrections, so the columns line up with negative values
+        #[arg(long)]
+        plus_sign: bool,
+    },
 
     /// Queries the current RS485 address, this message is broadcasted.
     /// Only one temperature module can be connected to the RS485 bus, more than one will be wrong!
@@ -221,6 +389,14 @@ enum CliCommands {
         /// Correction value in °Celsius
         #[arg(value_parser = parse_degree_celsius)]
         value: f32,
+
+        /// Reject corrections outside of the conservative range of ±10 °C instead of warning
+        #[arg(long)]
+        strict: bool,
+
+        /// Write to all devices with an address in this range like 1-8 instead of the connected address
+        #[arg(long, value_parser = parse_address_range)]
+        address_range: Option<RangeInclusive<u8>>,
     },
 
     /// Set the baud rate. After the command you need to power up the module again!
@@ -228,11 +404,15 @@ enum CliCommands {
         /// The new baud rate any value of 1200, 2400, 4800, 9600, 19200
         #[arg(value_parser = parse_baud_rate)]
         new_baud_rate: BaudRate,
+
+        /// Write to all devices with an address in this range like 1-8 instead of the connected address
+        #[arg(long, value_parser = parse_address_range)]
+        address_range: Option<RangeInclusive<u8>>,
     },
 
     /// Set the RS485 address
     SetAddress {
-        /// The RS485 address can be from 1 to 247
+        /// The RS485 address can be from 1 to 247, it must not be used by another device on the bus
         #[arg(value_parser = parse_address)]
         address: u8,
     },
@@ -241,6 +421,25 @@ enum CliCommands {
     SetAutomaticReport {
         /// Report time in seconds. 0 = disabled (default) or from 1 to 255 seconds.
         report_time: u8,
+
+        /// Treat a missing response as success, some firmware starts reporting without responding to the write
+        #[arg(long)]
+        ignore_timeout: bool,
+
+        /// Write to all devices with an address in this range like 1-8 instead of the connected address
+        #[arg(long, value_parser = parse_address_range)]
+        address_range: Option<RangeInclusive<u8>>,
+    },
+
+    /// Set temperature automatic reporting and listen for the reported temperatures (RTU only)
+    Listen {
+        /// Report interval from 1 to 255 seconds
+        #[arg(value_parser = humantime::parse_duration, short, long, default_value = "5sec")]
+        interval: Duration,
+
+        /// Treat a missing response to enabling the reports as success
+        #[arg(long)]
+        ignore_timeout: bool,
     },
 
     /// Reset the device to the factory default settings
@@ -271,6 +470,14 @@ struct CliArgs {
     /// Delay between multiple modbus commands
     #[arg(value_parser = humantime::parse_duration, long, default_value = "50ms")]
     delay: Duration,
+
+    /// Assume yes for all confirmation prompts, required for non-interactive usage
+    #[arg(short = 'y', long, visible_alias = "assume-yes", global = true)]
+    yes: bool,
+
+    /// Read the arguments from a TOML config file, must be the first argument
+    #[arg(long)]
+    config: Option<PathBuf>,
 }
 
 fn logging_init(loglevel: LevelFilter) -> LoggerHandle {
@@ -345,6 +552,629 @@ macro_rules! print_automatic_report {
     };
 }
 
+/// Formats register values as space separated hex words, e.g. "0x00db 0xffe2".
+fn format_registers_hex(registers: &[u16]) -> String {
+    registers
+        .iter()
+        .map(|register| format!("{register:#06x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Width of a full bar of [`format_bar_chart`] in characters.
+const BAR_CHART_WIDTH: usize = 40;
+
+/// Formats the temperatures as horizontal bars scaled between `min` and `max` °C, a full bar has `width` characters.
+///
+/// Values outside of the scale are clamped, channels without a sensor show "---".
+fn format_bar_chart(temperatures: &[f32], min: f32, max: f32, width: usize) -> String {
+    temperatures
+        .iter()
+        .enumerate()
+        .map(|(channel, value)| {
+            if value.is_nan() {
+                format!("{channel} | ---\n")
+            } else {
+                let fraction = ((value - min) / (max - min)).clamp(0.0, 1.0);
+                let bar = "#".repeat((fraction * width as f32).round() as usize);
+                format!("{channel} | {bar:<width$} {value:.1}\n")
+            }
+        })
+        .collect()
+}
+
+/// Formats temperatures and temperature corrections per channel as a table with aligned columns.
+///
+/// With `plus_sign` positive values are shown with a leading "+", e.g. "+21.9" and "-3.0".
+fn format_channel_table(temperatures: &[f32], corrections: &[f32], plus_sign: bool) -> String {
+    let format_value = |value: Option<&f32>| match value {
+        Some(value) if !value.is_nan() && plus_sign => format!("{value:+.1}"),
+        Some(value) if !value.is_nan() => format!("{value:.1}"),
+        _ => "-".to_string(),
+    };
+    let mut table = format!(
+        "{:>7} | {:>16} | {:>15}\n",
+        "Channel", "Temperature (°C)", "Correction (°C)"
+    );
+    table.push_str(&format!("{:-<7}-+-{:-<16}-+-{:-<15}\n", "", "", ""));
+    for channel in 0..temperatures.len().max(corrections.len()) {
+        table.push_str(&format!(
+            "{:>7} | {:>16} | {:>15}\n",
+            channel,
+            format_value(temperatures.get(channel)),
+            format_value(corrections.get(channel))
+        ));
+    }
+    table
+}
+
+/// Reads the channels round-robin, a subset per poll, and merges them into a snapshot of all channels.
+struct StaggeredSchedule {
+    channels_per_poll: u8,
+    next_channel: u8,
+    snapshot: Vec<f32>,
+}
+
+impl StaggeredSchedule {
+    fn new(channels_per_poll: u8) -> Self {
+        Self {
+            channels_per_poll,
+            next_channel: 0,
+            snapshot: vec![f32::NAN; proto::NUMBER_OF_CHANNELS as usize],
+        }
+    }
+
+    /// Returns the first channel and the number of channels due for the next poll.
+    fn due(&self) -> (u8, u8) {
+        let count = self
+            .channels_per_poll
+            .min(proto::NUMBER_OF_CHANNELS - self.next_channel);
+        (self.next_channel, count)
+    }
+
+    /// Merges the temperatures read beginning at channel `start` and returns the snapshot of all channels.
+    fn merge(&mut self, start: u8, temperatures: &[f32]) -> &[f32] {
+        for (offset, temperature) in temperatures.iter().enumerate() {
+            if let Some(value) = self.snapshot.get_mut(start as usize + offset) {
+                *value = *temperature;
+            }
+        }
+        self.next_channel = (start + temperatures.len() as u8) % proto::NUMBER_OF_CHANNELS;
+        &self.snapshot
+    }
+}
+
+/// Tracks the success rate and latency of polls over a sliding time window.
+struct HealthWindow {
+    window: Duration,
+    /// Time of the poll and its latency, `None` if the poll failed
+    samples: VecDeque<(Instant, Option<Duration>)>,
+    consecutive_failures: u32,
+}
+
+impl HealthWindow {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+            consecutive_failures: 0,
+        }
+    }
+
+    fn record(&mut self, now: Instant, latency: Option<Duration>) {
+        if latency.is_some() {
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures += 1;
+        }
+        self.samples.push_back((now, latency));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+}
+
+impl fmt::Display for HealthWindow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let latencies = self
+            .samples
+            .iter()
+            .filter_map(|(_, latency)| *latency)
+            .collect::<Vec<_>>();
+        let success_rate = if self.samples.is_empty() {
+            0.0
+        } else {
+            latencies.len() as f32 * 100.0 / self.samples.len() as f32
+        };
+        write!(
+            f,
+            "Success {:.1}% ({}/{}) | average latency ",
+            success_rate,
+            latencies.len(),
+            self.samples.len()
+        )?;
+        if latencies.is_empty() {
+            write!(f, "-")?;
+        } else {
+            let average = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+            write!(f, "{:?}", average)?;
+        }
+        write!(f, " | consecutive failures {}", self.consecutive_failures)
+    }
+}
+
+/// Formats the temperatures of the given channels as SenML JSON records (RFC 8428), NaN values are omitted.
+fn format_senml(temperatures: &[f32], channels: &[usize], time: SystemTime) -> String {
+    let base_time = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let records = channels
+        .iter()
+        .filter(|channel| {
+            temperatures
+                .get(**channel)
+                .is_some_and(|value| !value.is_nan())
+        })
+        .enumerate()
+        .map(|(index, channel)| {
+            let base = if index == 0 {
+                format!("\"bt\":{base_time:.3},")
+            } else {
+                String::new()
+            };
+            format!(
+                "{{{base}\"n\":\"channel{channel}\",\"u\":\"Cel\",\"v\":{}}}",
+                temperatures[*channel]
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", records.join(","))
+}
+
+/// Returns the environment variables passing the temperatures to the command of [`DaemonMode::Exec`].
+fn exec_environment(
+    temperatures: &[f32],
+    channels: &[usize],
+    stale: bool,
+) -> Vec<(String, String)> {
+    let mut environment = temperatures
+        .iter()
+        .enumerate()
+        .map(|(channel, value)| (format!("TEMPERATURE_{channel}"), value.to_string()))
+        .collect::<Vec<_>>();
+    let channels = channels.iter().map(ToString::to_string).collect::<Vec<_>>();
+    environment.push(("CHANNELS".to_string(), channels.join(",")));
+    environment.push(("STALE".to_string(), u8::from(stale).to_string()));
+    environment
+}
+
+/// Runs the command of [`DaemonMode::Exec`] and kills it after `timeout`.
+///
+/// Failures are logged, the daemon continues with the next reading.
+fn run_exec(command: &str, timeout: Duration, environment: Vec<(String, String)>) {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut process = std::process::Command::new(shell);
+    process.arg(flag).arg(command).envs(environment);
+    if let Err(error) = run_process(process, timeout) {
+        warn!("Command {command} failed: {error:#}");
+    }
+}
+
+/// Runs the process and kills it after `timeout`.
+///
+/// Returns an error if the process cannot be started, fails or is killed.
+fn run_process(mut process: std::process::Command, timeout: Duration) -> Result<()> {
+    let mut child = process
+        .stdin(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Cannot run {:?}", process.get_program()))?;
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if !status.success() {
+                bail!("{status}");
+            }
+            return Ok(());
+        }
+        if start.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+            bail!("Did not finish within {timeout:?} and was killed");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Formats the temperatures of all channels as a JSON object like `{"channel0":21.9,"channel1":null}`,
+/// NaN values are null.
+fn format_json_state(temperatures: &[f32]) -> String {
+    let channels = temperatures
+        .iter()
+        .enumerate()
+        .map(|(channel, value)| {
+            if value.is_nan() {
+                format!("\"channel{channel}\":null")
+            } else {
+                format!("\"channel{channel}\":{value}")
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", channels.join(","))
+}
+
+/// Formats a JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Formats the datagram of [`DaemonMode::Udp`], the device identifier, whether the reading is stale
+/// and the temperatures like [`format_json_state`].
+fn format_udp_datagram(device_id: &str, temperatures: &[f32], stale: bool) -> String {
+    let state = format_json_state(temperatures);
+    let channels = state.trim_start_matches('{').trim_end_matches('}');
+    let separator = if channels.is_empty() { "" } else { "," };
+    format!(
+        "{{\"device\":{},\"stale\":{stale}{separator}{channels}}}",
+        json_string(device_id)
+    )
+}
+
+/// Formats the state file of the daemon, the time of the reading in seconds since the Unix epoch
+/// and the temperatures separated by commas.
+fn format_state_file(temperatures: &[f32], time: SystemTime) -> String {
+    let temperatures = temperatures
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    format!(
+        "{} {}\n",
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        temperatures.join(",")
+    )
+}
+
+/// Parses the state file written by [`format_state_file`].
+fn parse_state_file(content: &str) -> Option<(SystemTime, Vec<f32>)> {
+    let (time, temperatures) = content.trim().split_once(' ')?;
+    let time = UNIX_EPOCH + Duration::from_secs(time.parse().ok()?);
+    let temperatures = temperatures
+        .split(',')
+        .map(str::parse)
+        .collect::<std::result::Result<Vec<f32>, _>>()
+        .ok()?;
+    Some((time, temperatures))
+}
+
+/// Loads the last reading from the state file, a missing or invalid file is logged and ignored.
+fn load_state_file(path: &Path) -> Option<(SystemTime, Vec<f32>)> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => {
+            let state = parse_state_file(&content);
+            if state.is_none() {
+                warn!("Ignore invalid state file {}", path.display());
+            }
+            state
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => {
+            warn!("Cannot read state file {}: {error}", path.display());
+            None
+        }
+    }
+}
+
+/// Returns the MQTT messages publishing the `channels` of the temperatures in the given format.
+fn mqtt_messages(
+    format: MqttFormat,
+    topic: &str,
+    qos: i32,
+    temperatures: &[f32],
+    channels: Vec<usize>,
+) -> Vec<mqtt::Message> {
+    match format {
+        MqttFormat::PerChannel => channels
+            .into_iter()
+            .map(|channel| {
+                mqtt::Message::new(
+                    format!("{topic}/{channel}"),
+                    temperatures[channel].to_string(),
+                    qos,
+                )
+            })
+            .collect(),
+        MqttFormat::Json if channels.is_empty() => Vec::new(),
+        MqttFormat::Json => vec![mqtt::Message::new_retained(
+            format!("{topic}/state"),
+            format_json_state(temperatures),
+            qos,
+        )],
+    }
+}
+
+/// Suppresses channels while their temperature stays within a deadband of the last published value.
+struct ChangeFilter {
+    deadband: f32,
+    heartbeat: Duration,
+    last_published: Vec<Option<(Instant, f32)>>,
+}
+
+impl ChangeFilter {
+    fn new(deadband: f32, heartbeat: Duration) -> Self {
+        Self {
+            deadband,
+            heartbeat,
+            last_published: vec![None; proto::NUMBER_OF_CHANNELS as usize],
+        }
+    }
+
+    /// Returns the channels to publish and records their temperatures as published.
+    ///
+    /// A channel is published if its temperature changed by more than the deadband, changed from or to NaN,
+    /// or was not published within the heartbeat.
+    fn due(&mut self, now: Instant, temperatures: &[f32]) -> Vec<usize> {
+        if self.last_published.len() < temperatures.len() {
+            self.last_published.resize(temperatures.len(), None);
+        }
+        let mut channels = Vec::new();
+        for (channel, temperature) in temperatures.iter().enumerate() {
+            let publish = match self.last_published[channel] {
+                None => true,
+                Some((time, published)) => {
+                    now.duration_since(time) >= self.heartbeat
+                        || published.is_nan() != temperature.is_nan()
+                        || (published - temperature).abs() > self.deadband
+                }
+            };
+            if publish {
+                self.last_published[channel] = Some((now, *temperature));
+                channels.push(channel);
+            }
+        }
+        channels
+    }
+}
+
+/// Tolerates a limited number of consecutive failures before an error is returned.
+struct FailureTolerance {
+    max_consecutive_failures: u32,
+    consecutive_failures: u32,
+}
+
+impl FailureTolerance {
+    fn new(max_consecutive_failures: u32) -> Self {
+        Self {
+            max_consecutive_failures,
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Returns the value on success, `None` on a tolerated failure and the error once the limit is exceeded.
+    fn check<T>(&mut self, result: Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => {
+                self.consecutive_failures = 0;
+                Ok(Some(value))
+            }
+            Err(error) if self.consecutive_failures < self.max_consecutive_failures => {
+                self.consecutive_failures += 1;
+                warn!(
+                    "{:#} (consecutive failure {} of {})",
+                    error, self.consecutive_failures, self.max_consecutive_failures
+                );
+                Ok(None)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Clears the consecutive failures, e.g. after the failure was handled.
+    fn reset(&mut self) {
+        self.consecutive_failures = 0;
+    }
+}
+
+/// Waits and reopens the connection, on failure the old connection is kept and polling continues.
+fn reopen(d: &mut R4DCB08, spec: &ConnectionSpec, timeout: Duration, wait: Duration) {
+    std::thread::sleep(wait);
+    match spec.connect(timeout) {
+        Ok(reopened) => {
+            info!("Reopened {}", spec);
+            *d = reopened;
+        }
+        Err(error) => warn!("Cannot reopen {}: {}", spec, error),
+    }
+}
+
+/// Upper limit of the wait between two attempts of [`connect_with_startup_retry`].
+const STARTUP_RETRY_MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Returns the wait after the failed `attempt` (starting at 1), `interval` doubled per attempt
+/// up to [`STARTUP_RETRY_MAX_INTERVAL`].
+fn startup_retry_backoff(interval: Duration, attempt: u32) -> Duration {
+    interval
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(STARTUP_RETRY_MAX_INTERVAL)
+}
+
+/// Opens the connection, retrying with backoff up to `attempts` times (0 retries forever).
+fn connect_with_startup_retry(
+    spec: &ConnectionSpec,
+    timeout: Duration,
+    attempts: u32,
+    interval: Duration,
+) -> Result<R4DCB08> {
+    let mut attempt = 1;
+    loop {
+        match spec.connect(timeout) {
+            Ok(d) => return Ok(d),
+            Err(error) if attempts == 0 || attempt < attempts => {
+                let wait = startup_retry_backoff(interval, attempt);
+                warn!("Cannot open {spec}: {error}, retry in {wait:?}");
+                std::thread::sleep(wait);
+                attempt += 1;
+            }
+            Err(error) => return Err(error).with_context(|| format!("Cannot open {}", spec)),
+        }
+    }
+}
+
+/// Runs the watchdog action for the error, only [`WatchdogAction::Exit`] returns the error.
+fn fire_watchdog(
+    action: WatchdogAction,
+    error: anyhow::Error,
+    d: &mut R4DCB08,
+    spec: &ConnectionSpec,
+    timeout: Duration,
+) -> Result<()> {
+    match action {
+        WatchdogAction::Exit => return Err(error),
+        WatchdogAction::Log => warn!("Watchdog: {:#}", error),
+        WatchdogAction::Reconnect => {
+            warn!("Watchdog: {:#}, reopen {}", error, spec);
+            reopen(d, spec, timeout, Duration::ZERO);
+        }
+    }
+    Ok(())
+}
+
+/// Detects a wedged device which keeps reporting exactly the same temperatures on all channels.
+struct StuckDetector {
+    stuck_polls: u32,
+    last: Vec<f32>,
+    unchanged_polls: u32,
+}
+
+impl StuckDetector {
+    fn new(stuck_polls: u32) -> Self {
+        Self {
+            stuck_polls,
+            last: Vec::new(),
+            unchanged_polls: 0,
+        }
+    }
+
+    /// Returns true once the temperatures were the same for `stuck_polls` polls, then counting starts again.
+    fn is_stuck(&mut self, temperatures: &[f32]) -> bool {
+        // compare the bits, so NaN equals NaN
+        let unchanged = self.last.len() == temperatures.len()
+            && self
+                .last
+                .iter()
+                .zip(temperatures)
+                .all(|(last, value)| last.to_bits() == value.to_bits());
+        if unchanged {
+            self.unchanged_polls += 1;
+        } else {
+            self.last = temperatures.to_vec();
+            self.unchanged_polls = 1;
+        }
+        if self.unchanged_polls >= self.stuck_polls {
+            self.unchanged_polls = 0;
+            self.last.clear();
+            return true;
+        }
+        false
+    }
+}
+
+/// Returns true if the error is caused by a device which is no longer present, e.g. an unplugged USB adapter.
+fn is_device_disconnected(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref(),
+        Some(r4dcb08_lib::tokio_error::Error::DeviceDisconnected(_))
+    )
+}
+
+fn read_daemon_temperature(
+    d: &mut R4DCB08,
+    schedule: &mut Option<StaggeredSchedule>,
+) -> Result<Vec<f32>> {
+    let context = |d: &R4DCB08| match d.slave_address() {
+        Some(address) => format!("Cannot read temperature from {address:#04x}"),
+        None => "Cannot read temperature".to_string(),
+    };
+    match schedule {
+        Some(schedule) => {
+            let (start, count) = schedule.due();
+            let rsp = d
+                .read_temperature_range(start, count)
+                .with_context(|| context(d))?;
+            Ok(schedule.merge(start, &rsp).to_vec())
+        }
+        None => d.read_temperature().with_context(|| context(d)),
+    }
+}
+
+/// Runs the write for every address of `address_range`, or once for the connected address without a range.
+///
+/// The writes are separated by `delay` and stop at the first failing address.
+fn for_each_address(
+    d: &mut R4DCB08,
+    address_range: &Option<RangeInclusive<u8>>,
+    delay: Duration,
+    mut write: impl FnMut(&mut R4DCB08) -> Result<()>,
+) -> Result<()> {
+    let Some(address_range) = address_range else {
+        return write(d);
+    };
+    for (index, address) in address_range.clone().enumerate() {
+        if index > 0 {
+            std::thread::sleep(delay);
+        }
+        d.set_slave_address(address);
+        write(d).with_context(|| format!("Failed for address {address:#04x}"))?;
+        println!("Address {address:#04x} done");
+    }
+    Ok(())
+}
+
+/// Returns the poll interval, but at least the estimated duration of reading the temperatures and the delay.
+///
+/// This avoids hammering the bus with a too short poll interval.
+fn check_poll_interval(
+    poll_interval: Duration,
+    delay: Duration,
+    connection: &CliConnection,
+) -> Duration {
+    let transaction = match connection {
+        CliConnection::Rtu { baud_rate, .. } => proto::estimate_read_transaction_duration(
+            baud_rate.bits_per_second(),
+            proto::READ_TEMPERATURE_REG_QUAN,
+        ),
+        _ => Duration::ZERO,
+    };
+    let min_poll_interval = delay.max(transaction);
+    if poll_interval < min_poll_interval {
+        warn!(
+            "Your poll interval of {:?} is below the minimum of {:?}, fallback to minimum",
+            poll_interval, min_poll_interval
+        );
+        return min_poll_interval;
+    }
+    poll_interval
+}
+
 fn check_rtu_delay(delay: Duration, baud_rate: &BaudRate) -> Duration {
     let min_rtu_delay = baud_rate.minimum_rtu_delay();
     if delay < min_rtu_delay {
@@ -357,12 +1187,17 @@ fn check_rtu_delay(delay: Duration, baud_rate: &BaudRate) -> Duration {
     delay
 }
 
-fn rtu_scan(device: &String, baud_rate: &BaudRate, args: &CliArgs) -> Result<u8> {
+fn rtu_scan(device: &String, baud_rate: &BaudRate, exclusive: bool, args: &CliArgs) -> Result<u8> {
     let mut d = R4DCB08::new(
         tokio_modbus::client::sync::rtu::connect_slave(
-            &r4dcb08_lib::tokio_serial::serial_port_builder(device, baud_rate.as_u16() as u32),
+            &r4dcb08_lib::tokio_serial::serial_port_builder(
+                device,
+                baud_rate.as_u16() as u32,
+                exclusive,
+            ),
             tokio_modbus::Slave(proto::READ_ADDRESS_BROADCAST_ADDRESS),
         )
+        .map_err(|error| r4dcb08_lib::tokio_serial::open_error(device, error))
         .with_context(|| format!("Cannot open device {} baud rate {}", device, baud_rate))?,
     );
     d.set_timeout(args.timeout);
@@ -372,8 +1207,77 @@ fn rtu_scan(device: &String, baud_rate: &BaudRate, args: &CliArgs) -> Result<u8>
     Ok(rsp)
 }
 
-fn confirm_only_one_module_connected() -> Result<bool> {
-    println!("Use this command only if ONLY ONE temperature module is connected to the RS485 bus!");
+/// A temperature collector found by [`CliConnection::RtuScan`].
+#[derive(Debug, Clone, PartialEq)]
+struct ScanResult {
+    address: u8,
+    baud_rate: BaudRate,
+}
+
+/// Scans all baud rates for a temperature collector.
+///
+/// `progress` is called with `None` before and with the result after each baud rate is scanned.
+fn rtu_scan_all(
+    device: &String,
+    exclusive: bool,
+    args: &CliArgs,
+    mut progress: impl FnMut(&BaudRate, Option<&Result<u8>>),
+) -> Option<ScanResult> {
+    for baud_rate in BaudRate::iter() {
+        let delay = check_rtu_delay(args.delay, &baud_rate);
+        progress(&baud_rate, None);
+        let rsp = rtu_scan(device, &baud_rate, exclusive, args);
+        progress(&baud_rate, Some(&rsp));
+        match rsp {
+            Ok(address) => return Some(ScanResult { address, baud_rate }),
+            Err(error) => {
+                trace!("{:?}", error);
+                std::thread::sleep(delay);
+            }
+        }
+    }
+    None
+}
+
+/// Formats the result of the RTU scan as JSON object.
+fn format_scan_json(result: Option<&ScanResult>) -> String {
+    match result {
+        Some(result) => format!(
+            "{{\"found\":true,\"address\":\"{:#04x}\",\"baud_rate\":{}}}",
+            result.address,
+            result.baud_rate.as_u16()
+        ),
+        None => "{\"found\":false}".to_string(),
+    }
+}
+
+/// Formats a serial port with its type, USB ports with vendor and product.
+fn format_serial_port(port: &tokio_serial::SerialPortInfo) -> String {
+    match &port.port_type {
+        tokio_serial::SerialPortType::UsbPort(usb) => {
+            let mut text = format!("{} USB {:04x}:{:04x}", port.port_name, usb.vid, usb.pid);
+            for info in [&usb.manufacturer, &usb.product, &usb.serial_number]
+                .into_iter()
+                .flatten()
+            {
+                text.push(' ');
+                text.push_str(info);
+            }
+            text
+        }
+        tokio_serial::SerialPortType::PciPort => format!("{} PCI", port.port_name),
+        tokio_serial::SerialPortType::BluetoothPort => format!("{} Bluetooth", port.port_name),
+        tokio_serial::SerialPortType::Unknown => port.port_name.clone(),
+    }
+}
+
+fn confirm(assume_yes: bool) -> Result<bool> {
+    if assume_yes {
+        return Ok(true);
+    }
+    if !stdin().is_terminal() {
+        bail!("Confirmation required but stdin is not a terminal, use --yes to confirm non-interactively");
+    }
     Ok(Confirm::new()
         .with_prompt("Do you want to continue?")
         .default(false)
@@ -381,63 +1285,320 @@ fn confirm_only_one_module_connected() -> Result<bool> {
         .interact()?)
 }
 
+/// Warns on stderr like the prompt, so stdout holds only the result, e.g. the JSON of the RTU scan.
+fn confirm_only_one_module_connected(assume_yes: bool) -> Result<bool> {
+    eprintln!(
+        "Use this command only if ONLY ONE temperature module is connected to the RS485 bus!"
+    );
+    confirm(assume_yes)
+}
+
+/// A value of the TOML subset read from config files.
+#[derive(Debug, Clone, PartialEq)]
+enum ConfigValue {
+    String(String),
+    /// Integer or float as written, without digit separators
+    Number(String),
+    Bool(bool),
+    Array(Vec<ConfigValue>),
+}
+
+impl ConfigValue {
+    fn parse(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Self> {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.peek() {
+            Some('"') => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => return Ok(Self::String(value)),
+                        Some('\\') => value.push(match chars.next() {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('r') => '\r',
+                            Some(c @ ('"' | '\\')) => c,
+                            c => bail!("Unsupported escape sequence \\{}", c.unwrap_or(' ')),
+                        }),
+                        Some(c) => value.push(c),
+                        None => bail!("Unterminated string"),
+                    }
+                }
+            }
+            Some('\'') => {
+                chars.next();
+                let value: String = chars.by_ref().take_while(|c| *c != '\'').collect();
+                Ok(Self::String(value))
+            }
+            Some('[') => {
+                chars.next();
+                let mut values = Vec::new();
+                loop {
+                    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                    if chars.next_if_eq(&']').is_some() {
+                        return Ok(Self::Array(values));
+                    }
+                    values.push(Self::parse(chars)?);
+                    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                    match chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Self::Array(values)),
+                        _ => bail!("Expected ',' or ']' in array"),
+                    }
+                }
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || "+-._".contains(*c)) {
+                    token.push(c);
+                }
+                match token.as_str() {
+                    "true" => Ok(Self::Bool(true)),
+                    "false" => Ok(Self::Bool(false)),
+                    _ => {
+                        let number = token.replace('_', "");
+                        if number.parse::<f64>().is_err() {
+                            bail!("Invalid value '{}'", token);
+                        }
+                        Ok(Self::Number(number))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the value as command line argument, the values of an array are joined with `,`.
+    ///
+    /// E.g. the daemon offsets `[0.5, 0, -1.2]` become `0.5,0,-1.2`.
+    fn to_arg(&self) -> Result<String> {
+        match self {
+            Self::String(value) | Self::Number(value) => Ok(value.clone()),
+            Self::Bool(value) => Ok(value.to_string()),
+            Self::Array(values) => Ok(values
+                .iter()
+                .map(|value| match value {
+                    Self::Array(_) => bail!("Nested arrays are not supported"),
+                    value => value.to_arg(),
+                })
+                .collect::<Result<Vec<_>>>()?
+                .join(",")),
+        }
+    }
+}
+
+/// Removes a comment from a line, `#` within strings is kept.
+fn strip_config_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..index],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// Parses a config file in a subset of TOML into `(table, key, value)` entries, top-level keys have an empty table.
+///
+/// Supported are `[table]` headers and `key = value` lines with strings, numbers, booleans and single-line arrays.
+fn parse_config(content: &str) -> Result<Vec<(String, String, ConfigValue)>> {
+    let mut table = String::new();
+    let mut entries = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = strip_config_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let entry = || -> Result<Option<(String, ConfigValue)>> {
+            if let Some(name) = line.strip_prefix('[') {
+                let Some(name) = name.strip_suffix(']') else {
+                    bail!("Expected ']'");
+                };
+                table = name.trim().to_string();
+                return Ok(None);
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("Expected 'key = value'");
+            };
+            let key = key.trim().trim_matches('"');
+            if key.is_empty() {
+                bail!("Missing key");
+            }
+            let mut chars = value.chars().peekable();
+            let value = ConfigValue::parse(&mut chars)?;
+            if chars.any(|c| !c.is_whitespace()) {
+                bail!("Unexpected characters after the value");
+            }
+            Ok(Some((key.to_string(), value)))
+        }()
+        .with_context(|| format!("Line {}", number + 1))?;
+        if let Some((key, value)) = entry {
+            entries.push((table.clone(), key, value));
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns the command line arguments expressed by a config file, see [`parse_config`] for the format.
+///
+/// Top-level keys are the global options, `true` is passed as flag. The `[connection]` table selects the
+/// connection with `type` (`tcp`, `rtu` or `rtu-scan`), its other keys are the options of the connection,
+/// the `address` of a Modbus/TCP connection is passed as positional argument. The top-level `command` is an
+/// array with the arguments of the command, e.g. `["daemon", "mqtt", "mqtt://localhost:1883"]`.
+fn config_args(content: &str) -> Result<Vec<String>> {
+    let mut global = Vec::new();
+    let mut connection_type = None;
+    let mut connection_entries = Vec::new();
+    let mut command = Vec::new();
+    for (table, key, value) in parse_config(content)? {
+        let option = format!("--{}", key.replace('_', "-"));
+        match (table.as_str(), key.as_str(), &value) {
+            ("", "command", ConfigValue::Array(args)) => {
+                for arg in args {
+                    command.push(arg.to_arg()?);
+                }
+            }
+            ("", "command", _) => bail!("The command must be an array of arguments"),
+            ("", _, ConfigValue::Bool(true)) => global.push(option),
+            ("", _, ConfigValue::Bool(false)) => {}
+            ("", _, value) => global.push(format!("{option}={}", value.to_arg()?)),
+            ("connection", "type", ConfigValue::String(value)) => {
+                connection_type = Some(value.clone())
+            }
+            ("connection", "type", _) => bail!("The connection type must be a string"),
+            // translated once the type is known, the keys of a table are unordered
+            ("connection", _, _) => connection_entries.push((key, value)),
+            (table, _, _) => bail!("Unknown table [{}]", table),
+        }
+    }
+    let Some(connection_type) = connection_type else {
+        bail!("Missing connection type, e.g. type = \"rtu\" in the [connection] table");
+    };
+    let mut connection = Vec::new();
+    let mut positional = Vec::new();
+    for (key, value) in connection_entries {
+        if key == "address" && connection_type == "tcp" {
+            positional.push(value.to_arg()?);
+        } else {
+            // the boolean options of the connections take a value
+            connection.push(format!("--{}={}", key.replace('_', "-"), value.to_arg()?));
+        }
+    }
+    Ok(global
+        .into_iter()
+        .chain(std::iter::once(connection_type))
+        .chain(connection)
+        .chain(positional)
+        .chain(command)
+        .collect())
+}
+
+/// Replaces a leading `--config <file>` argument by the arguments read from the config file.
+///
+/// Further arguments are appended, e.g. a command missing in the config file.
+fn expand_config(args: impl IntoIterator<Item = String>) -> Result<Vec<String>> {
+    let mut args = args.into_iter();
+    let mut expanded: Vec<String> = args.next().into_iter().collect();
+    let mut rest = args.peekable();
+    let path = match rest.peek().map(String::as_str) {
+        Some("--config") => {
+            rest.next();
+            rest.next()
+        }
+        Some(arg) if arg.starts_with("--config=") => {
+            rest.next().map(|arg| arg["--config=".len()..].to_string())
+        }
+        _ => None,
+    };
+    if let Some(path) = path {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read config file {}", path))?;
+        expanded.extend(
+            config_args(&content).with_context(|| format!("Cannot parse config file {}", path))?,
+        );
+    }
+    expanded.extend(rest);
+    Ok(expanded)
+}
+
 fn main() -> Result<()> {
-    let args = CliArgs::parse();
+    let args = CliArgs::parse_from(expand_config(std::env::args())?);
+    if args.config.is_some() {
+        bail!("--config must be the first argument");
+    }
 
     let mut delay = args.delay;
 
     let _log_handle = logging_init(args.verbose.log_level_filter());
 
-    if let CliConnection::RtuScan { device } = &args.connection {
-        if !confirm_only_one_module_connected()? {
+    if let CliConnection::ListPorts = &args.connection {
+        let ports = tokio_serial::available_ports().with_context(|| "Cannot list serial ports")?;
+        if ports.is_empty() {
+            println!("No serial ports found");
+        }
+        for port in ports {
+            println!("{}", format_serial_port(&port));
+        }
+        return Ok(());
+    }
+
+    if let CliConnection::RtuScan {
+        device,
+        exclusive,
+        format,
+    } = &args.connection
+    {
+        if !confirm_only_one_module_connected(args.yes)? {
             return Ok(());
         }
-        for baud_rate in BaudRate::iter() {
-            print!("Scan RTU {} baud rate {} ... ", device, baud_rate);
-            stdout().flush().unwrap();
-            let delay = check_rtu_delay(delay, &baud_rate);
-            match rtu_scan(device, &baud_rate, &args) {
-                Ok(address) => {
-                    println!("succeeded");
-                    println!("RS485 Address: {:#04x}", address);
-                    println!("Baud rate: {}", baud_rate);
-                    return Ok(());
-                }
-                Err(error) => {
-                    println!("failed");
-                    trace!("{:?}", error);
-                    std::thread::sleep(delay);
-                }
-            }
+        if *format == ScanFormat::Json {
+            let result = rtu_scan_all(device, *exclusive, &args, |_, _| {});
+            println!("{}", format_scan_json(result.as_ref()));
+            return Ok(());
         }
-        bail!(
-            "Cannot find connected temperature collector for device {}",
-            device
-        )
+        let result = rtu_scan_all(device, *exclusive, &args, |baud_rate, rsp| match rsp {
+            None => {
+                print!("Scan RTU {} baud rate {} ... ", device, baud_rate);
+                stdout().flush().unwrap();
+            }
+            Some(Ok(_)) => println!("succeeded"),
+            Some(Err(_)) => println!("failed"),
+        });
+        let Some(result) = result else {
+            bail!(
+                "Cannot find connected temperature collector for device {}",
+                device
+            )
+        };
+        println!("RS485 Address: {:#04x}", result.address);
+        println!("Baud rate: {}", result.baud_rate);
+        return Ok(());
     }
 
-    let (mut d, command) = match &args.connection {
+    let (spec, command) = match &args.connection {
         CliConnection::Tcp { address, command } => {
             let socket_addr = address
                 .parse()
                 .with_context(|| format!("Cannot parse address {}", address))?;
-            trace!("Open TCP address {}", socket_addr);
-            (
-                R4DCB08::new(
-                    tokio_modbus::client::sync::tcp::connect(socket_addr)
-                        .with_context(|| format!("Cannot open {:?}", socket_addr))?,
-                ),
-                command,
-            )
+            (ConnectionSpec::Tcp { addr: socket_addr }, command)
         }
         CliConnection::Rtu {
             device,
             baud_rate,
             address,
+            exclusive,
             command,
         } => {
             let address = if command == &CliCommands::QueryAddress {
-                if !confirm_only_one_module_connected()? {
+                if !confirm_only_one_module_connected(args.yes)? {
                     return Ok(());
                 }
                 if *address != proto::READ_ADDRESS_BROADCAST_ADDRESS {
@@ -451,86 +1612,307 @@ fn main() -> Result<()> {
             } else {
                 *address
             };
-            trace!(
-                "Open RTU {} address {:#04x} baud rate {}",
-                device,
-                address,
-                baud_rate
-            );
             delay = check_rtu_delay(delay, baud_rate);
             (
-                R4DCB08::new(
-                    tokio_modbus::client::sync::rtu::connect_slave(
-                        &r4dcb08_lib::tokio_serial::serial_port_builder(
-                            device,
-                            baud_rate.as_u16() as u32,
-                        ),
-                        tokio_modbus::Slave(address),
-                    )
-                    .with_context(|| {
-                        format!("Cannot open device {} baud rate {}", device, baud_rate)
-                    })?,
-                ),
+                ConnectionSpec::Rtu {
+                    device: device.clone(),
+                    baud_rate: baud_rate.as_u16() as u32,
+                    address,
+                    exclusive: *exclusive,
+                },
                 command,
             )
         }
-        CliConnection::RtuScan { .. } => unreachable!(),
+        CliConnection::RtuScan { .. } | CliConnection::ListPorts => unreachable!(),
     };
-    d.set_timeout(args.timeout);
+    trace!("Open {}", spec);
+    let (startup_attempts, startup_retry_interval) = match command {
+        CliCommands::Daemon {
+            startup_attempts,
+            startup_retry_interval,
+            ..
+        } => (*startup_attempts, *startup_retry_interval),
+        _ => (1, Duration::ZERO),
+    };
+    let mut d = connect_with_startup_retry(
+        &spec,
+        args.timeout,
+        startup_attempts,
+        startup_retry_interval,
+    )?;
 
     match command {
-        CliCommands::Daemon { poll_iterval, mode } => match mode {
-            DaemonMode::Stdout => loop {
-                print_temperature!(&mut d);
-                std::thread::sleep(delay.max(*poll_iterval));
-            },
-            DaemonMode::Mqtt {
-                url,
-                username,
-                password,
-                topic,
-                qos,
-            } => {
-                let mut cli =
-                    mqtt::Client::new(url.clone()).with_context(|| "Error creating MQTT client")?;
-
-                // Use 5sec timeouts for sync calls.
-                cli.set_timeout(Duration::from_secs(5));
-
-                let mut conn_builder = mqtt::ConnectOptionsBuilder::new();
-                let mut conn_builder = conn_builder
-                    .keep_alive_interval(Duration::from_secs(20))
-                    .clean_session(true);
-
-                if let Some(user_name) = username {
-                    conn_builder = conn_builder.user_name(user_name)
+        CliCommands::Daemon {
+            poll_iterval,
+            stagger,
+            max_consecutive_failures,
+            stuck_polls,
+            watchdog_action,
+            offsets,
+            on_change,
+            heartbeat,
+            state_file,
+            mode,
+            ..
+        } => {
+            let poll_interval = check_poll_interval(*poll_iterval, delay, &args.connection);
+            let mut schedule = stagger.map(StaggeredSchedule::new);
+            if offsets.len() > proto::NUMBER_OF_CHANNELS as usize {
+                bail!(
+                    "At most {} offsets can be given, one per channel",
+                    proto::NUMBER_OF_CHANNELS
+                );
+            }
+            let mut tolerance = FailureTolerance::new(*max_consecutive_failures);
+            let mut stuck_detector = stuck_polls.map(StuckDetector::new);
+            let mut poll = || -> Result<Option<Vec<f32>>> {
+                let rsp = read_daemon_temperature(&mut d, &mut schedule);
+                if rsp.as_ref().is_err_and(is_device_disconnected) {
+                    // Wait for the adapter to be enumerated again, then reopen the connection
+                    reopen(&mut d, &spec, args.timeout, poll_interval);
                 }
-                if let Some(password) = password {
-                    conn_builder = conn_builder.password(password)
+                let rsp = match tolerance.check(rsp) {
+                    Ok(rsp) => rsp,
+                    Err(error) => {
+                        tolerance.reset();
+                        fire_watchdog(*watchdog_action, error, &mut d, &spec, args.timeout)?;
+                        None
+                    }
+                };
+                if let (Some(temperatures), Some(stuck_detector)) = (&rsp, &mut stuck_detector) {
+                    if stuck_detector.is_stuck(temperatures) {
+                        let error = anyhow::anyhow!(
+                            "The temperatures did not change for {} polls",
+                            stuck_detector.stuck_polls
+                        );
+                        fire_watchdog(*watchdog_action, error, &mut d, &spec, args.timeout)?;
+                    }
                 }
-                let conn_ops = conn_builder.finalize();
-
-                // Connect and wait for it to complete or fail.
-                // The default connection uses MQTT v3.x
-                cli.connect(conn_ops)
-                    .with_context(|| "MQTT client unable to connect")?;
-
-                loop {
-                    let reply = d.read_temperature()?;
-                    trace!("Temperature: {:?}", reply);
-                    for (channel, temperature) in reply.iter().enumerate() {
-                        let topic = format!("{topic}/{channel}");
-                        let msg = mqtt::Message::new(topic, temperature.to_string(), *qos as i32);
-                        cli.publish(msg)
-                            .with_context(|| "Cannot publish MQTT message")?;
+                let rsp = rsp.map(|temperatures| {
+                    proto::apply_temperature_correction(&temperatures, offsets)
+                });
+                if let (Some(temperatures), Some(path)) = (&rsp, state_file) {
+                    let state = format_state_file(temperatures, SystemTime::now());
+                    if let Err(error) = std::fs::write(path, state) {
+                        warn!("Cannot write state file {}: {error}", path.display());
+                    }
+                }
+                Ok(rsp)
+            };
+            let stale = state_file.as_deref().and_then(load_state_file);
+            let all_channels = |temperatures: &[f32]| (0..temperatures.len()).collect::<Vec<_>>();
+            let mut change_filter =
+                on_change.map(|deadband| ChangeFilter::new(deadband, *heartbeat));
+            let mut due_channels = |temperatures: &[f32]| match &mut change_filter {
+                Some(change_filter) => change_filter.due(Instant::now(), temperatures),
+                None => (0..temperatures.len()).collect(),
+            };
+            match mode {
+                DaemonMode::Stdout => {
+                    if let Some((time, rsp)) = &stale {
+                        println!(
+                            "Stale temperatures in °C read at {}: {rsp:?}",
+                            humantime::format_rfc3339_seconds(*time)
+                        );
+                    }
+                    loop {
+                        if let Some(rsp) = poll()? {
+                            if !due_channels(&rsp).is_empty() {
+                                println!("Temperatures in °C: {rsp:?}");
+                            }
+                        }
+                        std::thread::sleep(poll_interval);
+                    }
+                }
+                DaemonMode::Senml => {
+                    // The time of the record marks the reading as stale
+                    if let Some((time, rsp)) = &stale {
+                        println!("{}", format_senml(rsp, &all_channels(rsp), *time));
+                    }
+                    loop {
+                        if let Some(rsp) = poll()? {
+                            let channels = due_channels(&rsp);
+                            if !channels.is_empty() {
+                                println!("{}", format_senml(&rsp, &channels, SystemTime::now()));
+                            }
+                        }
+                        std::thread::sleep(poll_interval);
+                    }
+                }
+                DaemonMode::Exec { command, timeout } => {
+                    if let Some((_, rsp)) = &stale {
+                        let environment = exec_environment(rsp, &all_channels(rsp), true);
+                        run_exec(command, *timeout, environment);
+                    }
+                    loop {
+                        if let Some(rsp) = poll()? {
+                            let channels = due_channels(&rsp);
+                            if !channels.is_empty() {
+                                let environment = exec_environment(&rsp, &channels, false);
+                                run_exec(command, *timeout, environment);
+                            }
+                        }
+                        std::thread::sleep(poll_interval);
+                    }
+                }
+                DaemonMode::Udp { target, device_id } => {
+                    let target = target
+                        .to_socket_addrs()
+                        .with_context(|| format!("Cannot resolve {target}"))?
+                        .next()
+                        .with_context(|| format!("Cannot resolve {target}"))?;
+                    let bind_addr: SocketAddr = if target.is_ipv4() {
+                        (Ipv4Addr::UNSPECIFIED, 0).into()
+                    } else {
+                        (Ipv6Addr::UNSPECIFIED, 0).into()
+                    };
+                    let socket =
+                        UdpSocket::bind(bind_addr).with_context(|| "Cannot open UDP socket")?;
+                    let device_id = device_id.clone().unwrap_or_else(|| spec.to_string());
+                    let send = |temperatures: &[f32], stale: bool| {
+                        let datagram = format_udp_datagram(&device_id, temperatures, stale);
+                        if let Err(error) = socket.send_to(datagram.as_bytes(), target) {
+                            warn!("Cannot send datagram to {target}: {error}");
+                        }
+                    };
+                    if let Some((_, rsp)) = &stale {
+                        send(rsp, true);
+                    }
+                    loop {
+                        if let Some(rsp) = poll()? {
+                            if !due_channels(&rsp).is_empty() {
+                                send(&rsp, false);
+                            }
+                        }
+                        std::thread::sleep(poll_interval);
+                    }
+                }
+                DaemonMode::Mqtt {
+                    url,
+                    username,
+                    password,
+                    topic,
+                    qos,
+                    format,
+                } => {
+                    let mut cli = mqtt::Client::new(url.clone())
+                        .with_context(|| "Error creating MQTT client")?;
+
+                    // Use 5sec timeouts for sync calls.
+                    cli.set_timeout(Duration::from_secs(5));
+
+                    let mut conn_builder = mqtt::ConnectOptionsBuilder::new();
+                    let mut conn_builder = conn_builder
+                        .keep_alive_interval(Duration::from_secs(20))
+                        .clean_session(true);
+
+                    if let Some(user_name) = username {
+                        conn_builder = conn_builder.user_name(user_name)
+                    }
+                    if let Some(password) = password {
+                        conn_builder = conn_builder.password(password)
+                    }
+                    let conn_ops = conn_builder.finalize();
+
+                    // Connect and wait for it to complete or fail.
+                    // The default connection uses MQTT v3.x
+                    cli.connect(conn_ops)
+                        .with_context(|| "MQTT client unable to connect")?;
+
+                    // {topic}/stale is "true" while the reading loaded from the state file is published
+                    let stale_message = |stale: bool| {
+                        mqtt::Message::new_retained(
+                            format!("{topic}/stale"),
+                            stale.to_string(),
+                            *qos as i32,
+                        )
+                    };
+                    if let Some((_, rsp)) = &stale {
+                        let mut messages =
+                            mqtt_messages(*format, topic, *qos as i32, rsp, all_channels(rsp));
+                        messages.push(stale_message(true));
+                        for msg in messages {
+                            cli.publish(msg)
+                                .with_context(|| "Cannot publish MQTT message")?;
+                        }
+                    }
+                    let mut is_stale = stale.is_some();
+                    loop {
+                        if let Some(reply) = poll()? {
+                            trace!("Temperature: {:?}", reply);
+                            let channels = due_channels(&reply);
+                            let mut messages =
+                                mqtt_messages(*format, topic, *qos as i32, &reply, channels);
+                            if is_stale {
+                                messages.push(stale_message(false));
+                                is_stale = false;
+                            }
+                            for msg in messages {
+                                cli.publish(msg)
+                                    .with_context(|| "Cannot publish MQTT message")?;
+                            }
+                        }
+                        std::thread::sleep(poll_interval);
                     }
-                    std::thread::sleep(delay.max(*poll_iterval));
                 }
             }
-        },
-        CliCommands::Read => {
+        }
+        CliCommands::Health {
+            window,
+            poll_interval,
+        } => {
+            let poll_interval = check_poll_interval(*poll_interval, delay, &args.connection);
+            let mut health = HealthWindow::new(*window);
+            loop {
+                let start = Instant::now();
+                let latency = match d.read_temperature() {
+                    Ok(_) => Some(start.elapsed()),
+                    Err(error) => {
+                        debug!("Cannot read temperature: {}", error);
+                        None
+                    }
+                };
+                health.record(Instant::now(), latency);
+                println!("{health}");
+                std::thread::sleep(poll_interval);
+            }
+        }
+        CliCommands::Read {
+            chart: true,
+            chart_min,
+            chart_max,
+            ..
+        } => {
+            if chart_min >= chart_max {
+                bail!("The chart minimum must be below the maximum");
+            }
+            let rsp = d
+                .read_temperature()
+                .with_context(|| "Cannot read temperature")?;
+            print!(
+                "{}",
+                format_bar_chart(&rsp, *chart_min, *chart_max, BAR_CHART_WIDTH)
+            );
+        }
+        CliCommands::Read { raw: false, .. } => {
             print_temperature!(&mut d);
         }
+        CliCommands::Read { raw: true, .. } => {
+            let (temperatures, registers) = d
+                .read_temperatures_with_raw()
+                .with_context(|| "Cannot read temperature")?;
+            println!("Temperatures in °C: {temperatures:?}");
+            println!("Registers: {}", format_registers_hex(&registers));
+        }
+        CliCommands::ReadRange { start, count } => {
+            let rsp = d
+                .read_temperature_range(*start, *count)
+                .with_context(|| "Cannot read temperature")?;
+            println!(
+                "Temperatures of channels {start} to {} in °C: {rsp:?}",
+                start + count - 1
+            );
+        }
         CliCommands::ReadCorrection => {
             print_temperature_correction!(&mut d);
         }
@@ -540,14 +1922,28 @@ fn main() -> Result<()> {
         CliCommands::ReadAutomaticReport => {
             print_automatic_report!(&mut d);
         }
-        CliCommands::ReadAll => {
-            print_temperature!(&mut d);
-            std::thread::sleep(delay);
-            print_temperature_correction!(&mut d);
+        CliCommands::ReadAll { plus_sign } => {
+            let temperatures = d
+                .read_temperature()
+                .with_context(|| "Cannot read temperature")?;
             std::thread::sleep(delay);
-            print_baud_rate!(&mut d);
+            let corrections = d
+                .read_temperature_correction()
+                .with_context(|| "Cannot read temperature correction")?;
+            print!(
+                "{}",
+                format_channel_table(&temperatures, &corrections, *plus_sign)
+            );
             std::thread::sleep(delay);
-            print_automatic_report!(&mut d);
+            let config = d
+                .read_config_block()
+                .with_context(|| "Cannot read configuration")?;
+            println!("RS485 address: {:#04x}", config.address);
+            println!("Baud rate: {}", BaudRate::from(config.baud_rate));
+            println!(
+                "Automatic report in seconds (0 means disabled): {}",
+                config.automatic_report.as_secs()
+            );
         }
         CliCommands::QueryAddress => {
             let rsp = d
@@ -555,24 +1951,108 @@ fn main() -> Result<()> {
                 .with_context(|| "Cannot read RS485 address")?;
             println!("RS485 address: {:#04x}", rsp);
         }
-        CliCommands::SetCorrection { channel, value } => {
-            d.set_temperature_correction(*channel, *value)
-                .with_context(|| "Cannot set temperature correction")?;
+        CliCommands::SetCorrection {
+            channel,
+            value,
+            strict,
+            address_range,
+        } => {
+            if !*strict && !proto::correction_in_range(*value) {
+                warn!(
+                    "The correction {value} is outside the conservative range of {} to {}",
+                    proto::CORRECTION_MIN,
+                    proto::CORRECTION_MAX
+                );
+            }
+            d.set_strict_correction_range(*strict);
+            for_each_address(&mut d, address_range, delay, |d| {
+                d.set_temperature_correction(*channel, *value)
+                    .with_context(|| "Cannot set temperature correction")
+            })?;
         }
-        CliCommands::SetBaudRate { new_baud_rate } => {
-            d.set_baud_rate(**new_baud_rate)
-                .with_context(|| "Cannot set baud rate")?;
+        CliCommands::SetBaudRate {
+            new_baud_rate,
+            address_range,
+        } => {
+            for_each_address(&mut d, address_range, delay, |d| {
+                d.set_baud_rate(**new_baud_rate)
+                    .with_context(|| "Cannot set baud rate")
+            })?;
             println!("The baud rate will be updated when the module is powered up again!");
         }
         CliCommands::SetAddress { address } => {
+            if d.slave_address() != Some(*address) {
+                if d.is_address_in_use(*address, args.timeout)
+                    .with_context(|| "Cannot check RS485 address")?
+                {
+                    bail!("The RS485 address {address:#04x} is already used by another device");
+                }
+                std::thread::sleep(delay);
+            }
             d.set_address(*address)
                 .with_context(|| "Cannot set RS485 address")?;
         }
         CliCommands::SetAutomaticReport {
             report_time: report_in_seconds,
+            ignore_timeout,
+            address_range,
+        } => {
+            d.set_ignore_automatic_report_timeout(*ignore_timeout);
+            for_each_address(&mut d, address_range, delay, |d| {
+                d.set_automatic_report(Duration::from_secs(*report_in_seconds as u64))
+                    .with_context(|| "Cannot set automatic report")
+            })?;
+        }
+        CliCommands::Listen {
+            interval,
+            ignore_timeout,
         } => {
-            d.set_automatic_report(Duration::from_secs(*report_in_seconds as u64))
+            let CliConnection::Rtu {
+                device,
+                baud_rate,
+                exclusive,
+                ..
+            } = &args.connection
+            else {
+                bail!("Listening for automatic reports requires a RTU connection");
+            };
+            if interval.as_secs() == 0 {
+                bail!("The report interval must be at least 1 second");
+            }
+            d.set_ignore_automatic_report_timeout(*ignore_timeout);
+            d.set_automatic_report(*interval)
                 .with_context(|| "Cannot set automatic report")?;
+            // Release the serial port for the listener
+            d.close();
+            let mut listener = r4dcb08_lib::tokio_serial::AutoReportListener::open(
+                device,
+                baud_rate.as_u16() as u32,
+                *exclusive,
+            )
+            .with_context(|| format!("Cannot open device {} baud rate {}", device, baud_rate))?;
+            // Tolerate one missed report before warning
+            let grace = *interval + args.timeout;
+            let mut last_report = Instant::now();
+            loop {
+                let deadline = proto::automatic_report_deadline(last_report, *interval, grace)
+                    .expect("The report interval is not zero");
+                match listener.next_frame(deadline.saturating_duration_since(Instant::now())) {
+                    Ok((address, temperatures)) => {
+                        last_report = Instant::now();
+                        println!("Temperatures in °C from {address:#04x}: {temperatures:?}");
+                    }
+                    Err(r4dcb08_lib::tokio_error::Error::ModbusError(
+                        tokio_modbus::Error::Transport(error),
+                    )) if error.kind() == std::io::ErrorKind::TimedOut => {
+                        warn!(
+                            "No automatic report received within {:?}",
+                            last_report.elapsed()
+                        );
+                        last_report = Instant::now();
+                    }
+                    Err(error) => return Err(error.into()),
+                }
+            }
         }
         CliCommands::FactoryReset => {
             println!("\
@@ -582,12 +2062,7 @@ fn main() -> Result<()> {
                 After this operation, the device will no longer be responsive!\n\
                 You must power off and on again to complete the reset.", proto::FACTORY_DEFAULT_ADDRESS, BaudRate::from(*proto::FACTORY_DEFAULT_BAUD_RATE)
             );
-            let confirmation = Confirm::new()
-                .with_prompt("Do you want to continue?")
-                .default(false)
-                .show_default(true)
-                .interact()?;
-            if !confirmation {
+            if !confirm(args.yes)? {
                 return Ok(());
             }
             print!("Check connection to temperature collector ... ");
@@ -646,4 +2121,504 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn staggered_schedule() {
+        let mut schedule = StaggeredSchedule::new(3);
+        assert_eq!(schedule.due(), (0, 3));
+        let snapshot = schedule.merge(0, &[1.0, 2.0, 3.0]);
+        assert_eq!(snapshot[..3], [1.0, 2.0, 3.0]);
+        assert!(snapshot[3..].iter().all(|value| value.is_nan()));
+
+        assert_eq!(schedule.due(), (3, 3));
+        schedule.merge(3, &[4.0, 5.0, 6.0]);
+
+        // the last poll of a round is truncated at the last channel
+        assert_eq!(schedule.due(), (6, 2));
+        let snapshot = schedule.merge(6, &[7.0, 8.0]);
+        assert_eq!(snapshot, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+
+        assert_eq!(schedule.due(), (0, 3));
+        let snapshot = schedule.merge(0, &[f32::NAN, 12.0, 13.0]);
+        assert!(snapshot[0].is_nan());
+        assert_eq!(snapshot[1..], [12.0, 13.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+    }
+
+    #[test]
+    fn config_args() {
+        let args = super::config_args(
+            "# Global options\n\
+             timeout = \"500ms\"\n\
+             yes = true\n\
+             command = [\"daemon\", \"--poll-iterval\", \"10s\", \"mqtt\", \"--password\", 'my # secret', \"mqtt://localhost:1883\"]\n\
+             \n\
+             [connection]\n\
+             type = \"rtu\" # RS485\n\
+             device = \"/dev/ttyUSB1\"\n\
+             baud_rate = 19_200\n\
+             address = 2\n\
+             exclusive = false\n",
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            [
+                "--timeout=500ms",
+                "--yes",
+                "rtu",
+                "--device=/dev/ttyUSB1",
+                "--baud-rate=19200",
+                "--address=2",
+                "--exclusive=false",
+                "daemon",
+                "--poll-iterval",
+                "10s",
+                "mqtt",
+                "--password",
+                "my # secret",
+                "mqtt://localhost:1883"
+            ]
+        );
+        let args =
+            CliArgs::try_parse_from(std::iter::once("tempcol".to_string()).chain(args)).unwrap();
+        assert!(matches!(
+            args.connection,
+            CliConnection::Rtu {
+                address: 2,
+                exclusive: false,
+                command: CliCommands::Daemon { .. },
+                ..
+            }
+        ));
+        assert_eq!(args.timeout, Duration::from_millis(500));
+
+        // an array within the command is one comma-separated argument
+        let args = super::config_args(
+            "command = [\"daemon\", \"--offsets\", [-0.5, 0, 1.2, 0, 0, 0, 0, 0.25], \"stdout\"]\n\
+             [connection]\n\
+             type = \"rtu\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            [
+                "rtu",
+                "daemon",
+                "--offsets",
+                "-0.5,0,1.2,0,0,0,0,0.25",
+                "stdout"
+            ]
+        );
+        let args =
+            CliArgs::try_parse_from(std::iter::once("tempcol".to_string()).chain(args)).unwrap();
+        let CliConnection::Rtu {
+            command: CliCommands::Daemon { offsets, .. },
+            ..
+        } = args.connection
+        else {
+            panic!("Daemon command expected");
+        };
+        assert_eq!(offsets, vec![-0.5, 0.0, 1.2, 0.0, 0.0, 0.0, 0.0, 0.25]);
+
+        // the address of a TCP connection is positional
+        let args =
+            super::config_args("[connection]\ntype = 'tcp'\naddress = \"192.168.0.222:502\"\n")
+                .unwrap();
+        assert_eq!(args, ["tcp", "192.168.0.222:502"]);
+        let args =
+            super::config_args("[connection]\naddress = \"192.168.0.222:502\"\ntype = 'tcp'\n")
+                .unwrap();
+        assert_eq!(args, ["tcp", "192.168.0.222:502"]);
+        let path = std::env::temp_dir().join(format!("tempcol-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[connection]\ntype = 'tcp'\naddress = \"192.168.0.222:502\"\n",
+        )
+        .unwrap();
+        let args = expand_config(
+            ["tempcol", "--config", path.to_str().unwrap(), "read"]
+                .into_iter()
+                .map(String::from),
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            args.unwrap(),
+            ["tempcol", "tcp", "192.168.0.222:502", "read"]
+        );
+
+        assert!(super::config_args("[connection]\ndevice = \"/dev/ttyUSB0\"\n").is_err());
+        assert!(super::config_args("[connection]\ntype = \"rtu\n").is_err());
+        assert!(super::config_args("[connection]\ntype = \"rtu\"\naddress = [[1]]\n").is_err());
+        assert!(super::config_args("[modbus]\ntype = \"rtu\"\n").is_err());
+        assert!(super::config_args("timeout\n").is_err());
+        assert!(super::config_args("timeout = 1s\n").is_err());
+    }
+
+    #[test]
+    fn health_window() {
+        let start = Instant::now();
+        let mut health = HealthWindow::new(Duration::from_secs(10));
+        assert_eq!(
+            health.to_string(),
+            "Success 0.0% (0/0) | average latency - | consecutive failures 0"
+        );
+
+        health.record(start, Some(Duration::from_millis(10)));
+        health.record(start + Duration::from_secs(5), None);
+        health.record(start + Duration::from_secs(6), None);
+        health.record(
+            start + Duration::from_secs(7),
+            Some(Duration::from_millis(30)),
+        );
+        assert_eq!(
+            health.to_string(),
+            "Success 50.0% (2/4) | average latency 20ms | consecutive failures 0"
+        );
+
+        // the first sample drops out of the window
+        health.record(start + Duration::from_secs(11), None);
+        assert_eq!(
+            health.to_string(),
+            "Success 25.0% (1/4) | average latency 30ms | consecutive failures 1"
+        );
+    }
+
+    #[test]
+    fn state_file() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let state = format_state_file(&[21.9, f32::NAN, -11.2], time);
+        assert_eq!(state, "1700000000 21.9,NaN,-11.2\n");
+        let (parsed_time, temperatures) = parse_state_file(&state).unwrap();
+        assert_eq!(parsed_time, time);
+        assert_eq!(temperatures[0], 21.9);
+        assert!(temperatures[1].is_nan());
+        assert_eq!(temperatures[2], -11.2);
+        assert!(parse_state_file("").is_none());
+        assert!(parse_state_file("1700000000 21.9,x").is_none());
+    }
+
+    #[test]
+    fn exec() {
+        assert_eq!(
+            exec_environment(&[21.9, f32::NAN], &[0], false),
+            [
+                ("TEMPERATURE_0".to_string(), "21.9".to_string()),
+                ("TEMPERATURE_1".to_string(), "NaN".to_string()),
+                ("CHANNELS".to_string(), "0".to_string()),
+                ("STALE".to_string(), "0".to_string()),
+            ]
+        );
+        #[cfg(unix)]
+        {
+            let start = Instant::now();
+            run_exec("sleep 10", Duration::from_millis(50), Vec::new());
+            assert!(start.elapsed() < Duration::from_secs(5));
+            run_exec("exit 1", Duration::from_secs(5), Vec::new());
+
+            let shell = |command: &str| {
+                let mut process = std::process::Command::new("sh");
+                process.arg("-c").arg(command);
+                process
+            };
+            assert!(run_process(shell("exit 0"), Duration::from_secs(5)).is_ok());
+            assert!(run_process(shell("exit 1"), Duration::from_secs(5)).is_err());
+            assert!(run_process(shell("sleep 10"), Duration::from_millis(50)).is_err());
+            assert!(run_process(
+                std::process::Command::new("/nonexistent/tempcol-hook"),
+                Duration::from_secs(5)
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn address_range() {
+        assert_eq!(parse_address_range("1-8"), Ok(1..=8));
+        assert_eq!(parse_address_range("0x10-0x12"), Ok(0x10..=0x12));
+        assert_eq!(parse_address_range("5-5"), Ok(5..=5));
+        assert!(parse_address_range("8-1").is_err());
+        assert!(parse_address_range("0-8").is_err());
+        assert!(parse_address_range("8").is_err());
+
+        let args = CliArgs::try_parse_from([
+            "tempcol",
+            "rtu",
+            "set-automatic-report",
+            "10",
+            "--address-range",
+            "1-8",
+        ])
+        .unwrap();
+        let CliConnection::Rtu {
+            command: CliCommands::SetAutomaticReport { address_range, .. },
+            ..
+        } = args.connection
+        else {
+            panic!("SetAutomaticReport expected");
+        };
+        assert_eq!(address_range, Some(1..=8));
+    }
+
+    #[test]
+    fn device_disconnected() {
+        let error = anyhow::Error::from(r4dcb08_lib::tokio_error::Error::DeviceDisconnected(
+            std::io::Error::from_raw_os_error(19),
+        ))
+        .context("Cannot read temperature");
+        assert!(is_device_disconnected(&error));
+        assert!(!is_device_disconnected(&anyhow::anyhow!("timeout")));
+    }
+
+    #[test]
+    fn udp_datagram() {
+        assert_eq!(
+            format_udp_datagram("RTU \"a\\b\"\n", &[21.9, f32::NAN], false),
+            r#"{"device":"RTU \"a\\b\"\u000a","stale":false,"channel0":21.9,"channel1":null}"#
+        );
+        assert_eq!(
+            format_udp_datagram("TCP", &[], true),
+            r#"{"device":"TCP","stale":true}"#
+        );
+    }
+
+    #[test]
+    fn scan_json() {
+        let result = ScanResult {
+            address: 0x01,
+            baud_rate: BaudRate::from(proto::BaudRate::B9600),
+        };
+        assert_eq!(
+            format_scan_json(Some(&result)),
+            r#"{"found":true,"address":"0x01","baud_rate":9600}"#
+        );
+        assert_eq!(format_scan_json(None), r#"{"found":false}"#);
+    }
+
+    #[test]
+    fn json_state() {
+        assert_eq!(
+            format_json_state(&[21.9, f32::NAN, -3.0]),
+            r#"{"channel0":21.9,"channel1":null,"channel2":-3}"#
+        );
+        assert_eq!(format_json_state(&[]), "{}");
+    }
+
+    #[test]
+    fn stuck_detector() {
+        let mut detector = StuckDetector::new(3);
+        assert!(!detector.is_stuck(&[21.9, f32::NAN]));
+        assert!(!detector.is_stuck(&[21.9, f32::NAN]));
+        assert!(detector.is_stuck(&[21.9, f32::NAN]));
+        // counting starts again after firing
+        assert!(!detector.is_stuck(&[21.9, f32::NAN]));
+        assert!(!detector.is_stuck(&[21.9, f32::NAN]));
+        // any change resets the detector
+        assert!(!detector.is_stuck(&[22.0, f32::NAN]));
+        assert!(!detector.is_stuck(&[22.0, f32::NAN]));
+        assert!(detector.is_stuck(&[22.0, f32::NAN]));
+    }
+
+    #[test]
+    fn registers_hex() {
+        assert_eq!(format_registers_hex(&[]), "");
+        assert_eq!(
+            format_registers_hex(&[0x00db, 0xffe2, 0x8000]),
+            "0x00db 0xffe2 0x8000"
+        );
+    }
+
+    #[test]
+    fn startup_retry() {
+        let interval = Duration::from_secs(1);
+        assert_eq!(startup_retry_backoff(interval, 1), interval);
+        assert_eq!(startup_retry_backoff(interval, 3), Duration::from_secs(4));
+        assert_eq!(
+            startup_retry_backoff(interval, 100),
+            STARTUP_RETRY_MAX_INTERVAL
+        );
+    }
+
+    #[test]
+    fn bar_chart() {
+        assert_eq!(
+            format_bar_chart(&[25.0, f32::NAN, -5.0, 80.0], 0.0, 50.0, 10),
+            [
+                "0 | #####      25.0",
+                "1 | ---",
+                "2 |            -5.0",
+                "3 | ########## 80.0",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn channel_table() {
+        assert_eq!(
+            format_channel_table(&[21.9, f32::NAN], &[0.0, -1.5], false),
+            [
+                "Channel | Temperature (°C) | Correction (°C)",
+                "--------+------------------+----------------",
+                "      0 |             21.9 |             0.0",
+                "      1 |                - |            -1.5",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            format_channel_table(&[21.9, -3.0], &[0.0, f32::NAN], true),
+            [
+                "Channel | Temperature (°C) | Correction (°C)",
+                "--------+------------------+----------------",
+                "      0 |            +21.9 |            +0.0",
+                "      1 |             -3.0 |               -",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn failure_tolerance() {
+        let mut tolerance = FailureTolerance::new(2);
+        assert_eq!(tolerance.check(Ok(1)).unwrap(), Some(1));
+        assert_eq!(
+            tolerance.check::<i32>(Err(anyhow::anyhow!("1"))).unwrap(),
+            None
+        );
+        assert_eq!(
+            tolerance.check::<i32>(Err(anyhow::anyhow!("2"))).unwrap(),
+            None
+        );
+        assert!(tolerance.check::<i32>(Err(anyhow::anyhow!("3"))).is_err());
+
+        // a success resets the consecutive failures
+        let mut tolerance = FailureTolerance::new(1);
+        assert_eq!(
+            tolerance.check::<i32>(Err(anyhow::anyhow!("1"))).unwrap(),
+            None
+        );
+        assert_eq!(tolerance.check(Ok(2)).unwrap(), Some(2));
+        assert_eq!(
+            tolerance.check::<i32>(Err(anyhow::anyhow!("2"))).unwrap(),
+            None
+        );
+
+        // the default gives up on the first failure
+        let mut tolerance = FailureTolerance::new(0);
+        assert!(tolerance.check::<i32>(Err(anyhow::anyhow!("1"))).is_err());
+    }
+
+    #[test]
+    fn change_filter() {
+        let start = Instant::now();
+        let mut filter = ChangeFilter::new(0.5, Duration::from_secs(60));
+        assert_eq!(filter.due(start, &[20.0, f32::NAN, 10.0]), vec![0, 1, 2]);
+
+        // within the deadband, NaN stays NaN
+        let now = start + Duration::from_secs(10);
+        assert!(filter.due(now, &[20.5, f32::NAN, 9.6]).is_empty());
+
+        // beyond the deadband of the last published value, from NaN to a value
+        let now = start + Duration::from_secs(20);
+        assert_eq!(filter.due(now, &[20.6, 15.0, 9.6]), vec![0, 1]);
+
+        // heartbeat of channel 2
+        let now = start + Duration::from_secs(60);
+        assert_eq!(filter.due(now, &[20.6, 15.0, 9.6]), vec![2]);
+
+        // from a value to NaN
+        let now = start + Duration::from_secs(61);
+        assert_eq!(filter.due(now, &[20.6, f32::NAN, 9.6]), vec![1]);
+    }
+
+    #[test]
+    fn senml() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+        assert_eq!(
+            format_senml(&[21.9, f32::NAN, -3.0], &[0, 1, 2], time),
+            "[{\"bt\":1700000000.250,\"n\":\"channel0\",\"u\":\"Cel\",\"v\":21.9},\
+             {\"n\":\"channel2\",\"u\":\"Cel\",\"v\":-3}]"
+        );
+        assert_eq!(format_senml(&[f32::NAN], &[0], time), "[]");
+        assert_eq!(
+            format_senml(&[f32::NAN, 5.5], &[1], time),
+            "[{\"bt\":1700000000.250,\"n\":\"channel1\",\"u\":\"Cel\",\"v\":5.5}]"
+        );
+    }
+
+    #[test]
+    fn poll_interval() {
+        let rtu = CliArgs::parse_from(["tempcol", "rtu", "--baud-rate", "1200", "read"]).connection;
+        let minimum = proto::estimate_read_transaction_duration(1200, 8);
+        assert_eq!(
+            check_poll_interval(Duration::ZERO, Duration::from_millis(50), &rtu),
+            minimum
+        );
+        assert_eq!(
+            check_poll_interval(Duration::from_secs(2), Duration::from_millis(50), &rtu),
+            Duration::from_secs(2)
+        );
+
+        let tcp = CliArgs::parse_from(["tempcol", "tcp", "127.0.0.1:502", "read"]).connection;
+        assert_eq!(
+            check_poll_interval(Duration::ZERO, Duration::from_millis(50), &tcp),
+            Duration::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn daemon_offsets() {
+        let args = CliArgs::parse_from([
+            "tempcol",
+            "tcp",
+            "127.0.0.1:502",
+            "daemon",
+            "--offsets",
+            "-0.5,0,1.2",
+            "stdout",
+        ]);
+        let CliConnection::Tcp {
+            command: CliCommands::Daemon { offsets, .. },
+            ..
+        } = args.connection
+        else {
+            panic!("Daemon command expected");
+        };
+        assert_eq!(offsets, vec![-0.5, 0.0, 1.2]);
+
+        let args = CliArgs::parse_from(["tempcol", "tcp", "127.0.0.1:502", "daemon", "stdout"]);
+        assert!(matches!(
+            args.connection,
+            CliConnection::Tcp {
+                command: CliCommands::Daemon { offsets, .. },
+                ..
+            } if offsets.is_empty()
+        ));
+    }
+
+    #[test]
+    fn serial_port() {
+        let port = tokio_serial::SerialPortInfo {
+            port_name: "/dev/ttyUSB0".to_string(),
+            port_type: tokio_serial::SerialPortType::UsbPort(tokio_serial::UsbPortInfo {
+                vid: 0x1a86,
+                pid: 0x7523,
+                serial_number: None,
+                manufacturer: Some("QinHeng Electronics".to_string()),
+                product: Some("CH340 serial converter".to_string()),
+            }),
+        };
+        assert_eq!(
+            format_serial_port(&port),
+            "/dev/ttyUSB0 USB 1a86:7523 QinHeng Electronics CH340 serial converter"
+        );
+
+        let port = tokio_serial::SerialPortInfo {
+            port_name: "/dev/ttyS0".to_string(),
+            port_type: tokio_serial::SerialPortType::Unknown,
+        };
+        assert_eq!(format_serial_port(&port), "/dev/ttyS0");
+    }
 }
@@ -1,5 +1,5 @@
 use crate::Error;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -12,6 +12,26 @@ pub enum BaudRate {
 }
 
 impl BaudRate {
+    /// All baud rates supported by the device, in ascending order.
+    pub const ALL: [BaudRate; 5] = [
+        BaudRate::B1200,
+        BaudRate::B2400,
+        BaudRate::B4800,
+        BaudRate::B9600,
+        BaudRate::B19200,
+    ];
+
+    /// Returns the baud rate in bits per second.
+    pub fn bits_per_second(&self) -> u32 {
+        match self {
+            BaudRate::B1200 => 1200,
+            BaudRate::B2400 => 2400,
+            BaudRate::B4800 => 4800,
+            BaudRate::B9600 => 9600,
+            BaudRate::B19200 => 19200,
+        }
+    }
+
     pub fn decode(value: u16) -> Self {
         match value {
             0 => BaudRate::B1200,
@@ -24,11 +44,36 @@ impl BaudRate {
         }
     }
 
+    /// Decodes the baud rate register without panicking on unknown values.
+    pub fn try_decode(value: u16) -> Result<Self, Error> {
+        match value {
+            0 => Ok(BaudRate::B1200),
+            1 => Ok(BaudRate::B2400),
+            2 => Ok(BaudRate::B4800),
+            3 => Ok(BaudRate::B9600),
+            4 => Ok(BaudRate::B19200),
+            _ => Err(Error::InvalidBaudRate(value)),
+        }
+    }
+
     pub fn encode(&self) -> u16 {
         *self as u16
     }
 }
 
+/// Estimates the duration of reading `quantity` holding registers with Modbus/RTU at `baud_rate` bits per second.
+///
+/// The estimate covers the request frame (8 bytes), the response frame (5 bytes plus 2 bytes per register)
+/// and the silent interval of 3.5 characters after each frame, a character is transmitted with 11 bits.
+/// The processing time of the device and the adapter is not included.
+pub fn estimate_read_transaction_duration(baud_rate: u32, quantity: u16) -> Duration {
+    let bytes = Framing::Rtu.adu_len(READ_REQUEST_PDU_LEN)
+        + Framing::Rtu.adu_len(read_response_pdu_len(quantity));
+    // 3.5 characters of 11 bits are 38.5 bits, twice 77 bits
+    let bits = bytes as u64 * 11 + 77;
+    Duration::from_nanos(bits * 1_000_000_000 / baud_rate as u64)
+}
+
 pub const NUMBER_OF_CHANNELS: u8 = 8;
 pub const FACTORY_DEFAULT_BAUD_RATE: &BaudRate = &BaudRate::B9600;
 pub const FACTORY_DEFAULT_ADDRESS: u8 = 0x01;
@@ -45,10 +90,19 @@ pub const READ_AUTOMATIC_REPORT_REG_ADDR: u16 = 0x00FD;
 pub const READ_AUTOMATIC_REPORT_REG_QUAN: u16 = 1;
 pub const WRITE_AUTOMATIC_REPORT_REG_ADDR: u16 = 0x00FD;
 
+/// The device pushes the temperatures of all channels like a response to reading the temperature registers:
+/// address (1), function code 0x03 (1), number of bytes (1), temperatures (16), CRC16 (2)
+pub const AUTOMATIC_REPORT_FUNCTION_CODE: u8 = 0x03;
+pub const AUTOMATIC_REPORT_FRAME_LEN: usize = 5 + 2 * NUMBER_OF_CHANNELS as usize;
+
 pub const READ_BAUD_RATE_REG_ADDR: u16 = 0x00FF;
 pub const READ_BAUD_RATE_REG_QUAN: u16 = 1;
 pub const WRITE_BAUD_RATE_REG_ADDR: u16 = 0x00FF;
 
+/// The automatic report, address and baud rate registers are contiguous, see [`read_config_block_decode`].
+pub const READ_CONFIG_BLOCK_REG_ADDR: u16 = READ_AUTOMATIC_REPORT_REG_ADDR;
+pub const READ_CONFIG_BLOCK_REG_QUAN: u16 = 3;
+
 pub const WRITE_FACTORY_RESET_REG_ADDR: u16 = 0x00FF;
 pub const WRITE_FACTORY_RESET_REG_DATA: u16 = 5;
 
@@ -56,22 +110,379 @@ pub const READ_ADDRESS_REG_ADDR: u16 = 0x00FE;
 pub const READ_ADDRESS_REG_QUAN: u16 = 1;
 pub const WRITE_ADDRESS_REG_ADDR: u16 = 0x00FE;
 
-pub fn degree_celsius_decode(value: u16) -> f32 {
-    match value.cmp(&0x8000) {
-        std::cmp::Ordering::Greater => {
-            // The highest bit 1 indicates a negative value，
-            // this value directly subtracting 65536 and divided by 10,
-            // is the current temperature value.
-            ((value as f32) - 65536.0) / 10.0
+/// A set of temperature channels 0 to 7.
+///
+/// The set is displayed and parsed as comma separated channels, e.g. `0,2,5`, the empty set as an empty string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChannelSet(u8);
+
+impl ChannelSet {
+    /// All channels 0 to 7.
+    pub const ALL: ChannelSet = ChannelSet(0xFF);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the channel, duplicates are ignored.
+    pub fn insert(&mut self, channel: u8) -> Result<(), Error> {
+        if !(CHANNELS_MIN..=CHANNELS_MAX).contains(&channel) {
+            return Err(Error::ChannelOutOfRange(channel));
+        }
+        self.0 |= 1 << channel;
+        Ok(())
+    }
+
+    pub fn contains(&self, channel: u8) -> bool {
+        channel <= CHANNELS_MAX && self.0 & (1 << channel) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Returns the channels in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (CHANNELS_MIN..=CHANNELS_MAX).filter(|channel| self.contains(*channel))
+    }
+}
+
+impl std::fmt::Display for ChannelSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (index, channel) in self.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", channel)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for ChannelSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = ChannelSet::new();
+        for channel in s
+            .split(',')
+            .map(str::trim)
+            .filter(|channel| !channel.is_empty())
+        {
+            set.insert(
+                channel
+                    .parse()
+                    .map_err(|_| Error::InvalidChannel(channel.to_string()))?,
+            )?;
+        }
+        Ok(set)
+    }
+}
+
+/// Holding register addresses used to access the device.
+///
+/// The default is the register map of the R4DCB08.
+/// Clones of the device may place the registers at other addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterLayout {
+    pub temperature_reg_addr: u16,
+    pub temperature_correction_reg_addr: u16,
+    pub automatic_report_reg_addr: u16,
+    pub address_reg_addr: u16,
+    pub baud_rate_reg_addr: u16,
+    pub factory_reset_reg_addr: u16,
+}
+
+impl Default for RegisterLayout {
+    fn default() -> Self {
+        Self {
+            temperature_reg_addr: READ_TEMPERATURE_REG_ADDR,
+            temperature_correction_reg_addr: READ_TEMPERATURE_CORRECTION_REG_ADDR,
+            automatic_report_reg_addr: READ_AUTOMATIC_REPORT_REG_ADDR,
+            address_reg_addr: READ_ADDRESS_REG_ADDR,
+            baud_rate_reg_addr: READ_BAUD_RATE_REG_ADDR,
+            factory_reset_reg_addr: WRITE_FACTORY_RESET_REG_ADDR,
+        }
+    }
+}
+
+impl RegisterLayout {
+    /// Checks that all registers are within the Modbus address space and do not overlap.
+    ///
+    /// Only the baud rate and factory reset registers may share an address, as on the R4DCB08.
+    pub fn validate(&self) -> Result<(), Error> {
+        // name, address, quantity and whether the register may share its address with the other shared ones
+        let registers = [
+            (
+                "temperature",
+                self.temperature_reg_addr,
+                READ_TEMPERATURE_REG_QUAN,
+                false,
+            ),
+            (
+                "temperature correction",
+                self.temperature_correction_reg_addr,
+                READ_TEMPERATURE_CORRECTION_REG_QUAN,
+                false,
+            ),
+            (
+                "automatic report",
+                self.automatic_report_reg_addr,
+                READ_AUTOMATIC_REPORT_REG_QUAN,
+                false,
+            ),
+            (
+                "address",
+                self.address_reg_addr,
+                READ_ADDRESS_REG_QUAN,
+                false,
+            ),
+            (
+                "baud rate",
+                self.baud_rate_reg_addr,
+                READ_BAUD_RATE_REG_QUAN,
+                true,
+            ),
+            ("factory reset", self.factory_reset_reg_addr, 1, true),
+        ];
+        // the last register of each range, as u32 to detect exceeding the address space
+        let end = |start: u16, quantity: u16| start as u32 + quantity as u32 - 1;
+        for (name, start, quantity, _) in registers {
+            if end(start, quantity) > u16::MAX as u32 {
+                return Err(Error::InvalidRegisterLayout(format!(
+                    "the {name} registers at {start:#06x} exceed the Modbus address space"
+                )));
+            }
         }
-        std::cmp::Ordering::Less => {
-            // The highest bit is 0, so the temperature is positive,
-            // it is converted to decimal and divided by 10
-            value as f32 / 10.0
+        for (index, (name, start, quantity, shareable)) in registers.iter().enumerate() {
+            for (other_name, other_start, other_quantity, other_shareable) in
+                &registers[index + 1..]
+            {
+                let overlap = *start as u32 <= end(*other_start, *other_quantity)
+                    && *other_start as u32 <= end(*start, *quantity);
+                let shared = *shareable && *other_shareable;
+                if overlap && !shared {
+                    return Err(Error::InvalidRegisterLayout(format!(
+                        "the {name} registers at {start:#06x} overlap the {other_name} registers at {other_start:#06x}"
+                    )));
+                }
+            }
         }
-        std::cmp::Ordering::Equal => {
-            // When the data is 0X8000(32768), it indicates no sensor or error
-            f32::NAN
+        Ok(())
+    }
+
+    /// Returns true if the automatic report, address and baud rate registers are contiguous
+    /// and can be read with a single request.
+    pub fn config_block_contiguous(&self) -> bool {
+        self.address_reg_addr == self.automatic_report_reg_addr.wrapping_add(1)
+            && self.baud_rate_reg_addr == self.automatic_report_reg_addr.wrapping_add(2)
+    }
+}
+
+/// Modbus function "Report Server ID", the response format is device specific and not documented for the R4DCB08.
+pub const REPORT_SLAVE_ID_FUNCTION_CODE: u8 = 0x11;
+
+pub const DIAGNOSTICS_FUNCTION_CODE: u8 = 0x08;
+pub const DIAGNOSTICS_RETURN_BUS_MESSAGE_COUNT: u16 = 0x000B;
+pub const DIAGNOSTICS_RETURN_BUS_COMMUNICATION_ERROR_COUNT: u16 = 0x000C;
+
+/// Counters of the Modbus diagnostics function (0x08).
+///
+/// A counter is `None` if the device does not support the sub-function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Diagnostics {
+    /// Number of messages the device detected on the bus since its last restart.
+    pub bus_message_count: Option<u16>,
+    /// Number of CRC errors the device detected since its last restart.
+    pub bus_communication_error_count: Option<u16>,
+}
+
+/// Encodes the request data of a diagnostics sub-function, which is the sub-function followed by zero data.
+pub fn diagnostics_encode_request(sub_function: u16) -> [u8; 4] {
+    let [hi, lo] = sub_function.to_be_bytes();
+    [hi, lo, 0x00, 0x00]
+}
+
+/// Decodes the counter of a diagnostics response.
+///
+/// Returns `None` if the response does not echo the sub-function or is malformed.
+pub fn diagnostics_decode_response(sub_function: u16, data: &[u8]) -> Option<u16> {
+    match data {
+        [hi, lo, counter_hi, counter_lo] if u16::from_be_bytes([*hi, *lo]) == sub_function => {
+            Some(u16::from_be_bytes([*counter_hi, *counter_lo]))
+        }
+        _ => None,
+    }
+}
+
+pub fn degree_celsius_decode(value: u16) -> f32 {
+    // The register holds the signed temperature in tenths of a degree (two's complement).
+    degree_celsius_from_tenths(value as i16)
+}
+
+/// Converts signed tenths of a degree Celsius into °C.
+///
+/// This mirrors [`degree_celsius_decode`] for data sources which already provide a signed integer.
+/// The bit pattern 0x8000 (`i16::MIN`) indicates no sensor or error and is mapped to NaN.
+pub fn degree_celsius_from_tenths(tenths: i16) -> f32 {
+    if tenths == i16::MIN {
+        // When the data is 0X8000(32768), it indicates no sensor or error
+        f32::NAN
+    } else {
+        tenths as f32 / 10.0
+    }
+}
+
+/// Decodes the register value into signed tenths of a degree Celsius without a float conversion.
+///
+/// Returns `None` for 0x8000, which indicates no sensor or an error.
+pub fn degree_celsius_decode_tenths(value: u16) -> Option<i16> {
+    Some(value as i16).filter(|tenths| *tenths != i16::MIN)
+}
+
+/// Converts signed tenths of a degree Celsius into the register value, the inverse of [`degree_celsius_from_tenths`].
+///
+/// Unlike [`degree_celsius_encode`] no float is involved, so test vectors and simulators get the exact register value.
+/// Note that `i16::MIN` results in 0x8000, which the device uses for no sensor or error.
+pub fn degree_celsius_encode_tenths(tenths: i16) -> u16 {
+    tenths as u16
+}
+
+/// Interpretation of the register value 0x8000 when decoding.
+///
+/// The R4DCB08 uses 0x8000 to indicate no sensor or an error for temperatures.
+/// Some firmware stores corrections using the full signed range, where 0x8000 is -3276.8 °C.
+/// Note the asymmetry: [`degree_celsius_encode`] never produces 0x8000, neither NaN nor -3276.8 °C can be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SentinelDecoding {
+    /// 0x8000 indicates no sensor or an error and is decoded as NaN.
+    #[default]
+    Nan,
+    /// 0x8000 is decoded as -3276.8 °C like any other two's complement value.
+    Signed,
+}
+
+/// Status of a temperature channel.
+///
+/// Note: The R4DCB08 reports a missing sensor and a sensor read error with the same register value 0x8000,
+/// it is decoded as [`ChannelStatus::NoSensor`]. [`ChannelStatus::Error`] is reserved for devices or firmware
+/// distinguishing both cases and is never returned for the R4DCB08.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelStatus {
+    /// The temperature in °C.
+    Ok(f32),
+    /// No sensor is connected, or the sensor could not be read.
+    NoSensor,
+    /// The sensor could not be read.
+    Error,
+}
+
+/// Decodes a temperature register value into the status of the channel.
+pub fn channel_status_decode(value: u16) -> ChannelStatus {
+    let temperature = degree_celsius_decode(value);
+    if temperature.is_nan() {
+        ChannelStatus::NoSensor
+    } else {
+        ChannelStatus::Ok(temperature)
+    }
+}
+
+/// Borrows the temperature registers of a response and decodes the channels on access.
+///
+/// Useful when scanning many devices where most channels are ignored, nothing is decoded or copied upfront.
+/// The registers are expected as received, set the [`ByteOrder`] of the gateway with [`Self::with_byte_order`],
+/// e.g. for the registers returned by `read_temperatures_with_raw`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemperaturesView<'a> {
+    words: &'a [u16],
+    byte_order: ByteOrder,
+    sentinel: SentinelDecoding,
+}
+
+impl<'a> TemperaturesView<'a> {
+    /// Wraps the register values, channel 0 first.
+    pub fn new(words: &'a [u16]) -> Self {
+        Self {
+            words,
+            byte_order: ByteOrder::default(),
+            sentinel: SentinelDecoding::default(),
+        }
+    }
+
+    /// Returns the view decoding the registers with `byte_order`.
+    pub fn with_byte_order(self, byte_order: ByteOrder) -> Self {
+        Self { byte_order, ..self }
+    }
+
+    /// Returns the view decoding 0x8000 according to `sentinel`.
+    pub fn with_sentinel(self, sentinel: SentinelDecoding) -> Self {
+        Self { sentinel, ..self }
+    }
+
+    /// Returns the temperature of `channel` in °C, NaN if no sensor is connected, or `None` if the channel is not in the response.
+    pub fn get(&self, channel: u8) -> Option<f32> {
+        self.words
+            .get(channel as usize)
+            .map(|word| self.decode(*word))
+    }
+
+    /// Returns the number of channels.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Returns the register values as received.
+    pub fn raw(&self) -> &'a [u16] {
+        self.words
+    }
+
+    /// Iterates over the temperatures in °C.
+    pub fn iter(&self) -> impl Iterator<Item = f32> + 'a {
+        let view = *self;
+        self.words.iter().map(move |word| view.decode(*word))
+    }
+
+    fn decode(&self, word: u16) -> f32 {
+        degree_celsius_decode_with(self.byte_order.apply(word), self.sentinel)
+    }
+}
+
+/// Decodes a temperature register value using the given interpretation of 0x8000.
+pub fn degree_celsius_decode_with(value: u16, sentinel: SentinelDecoding) -> f32 {
+    match sentinel {
+        SentinelDecoding::Nan => degree_celsius_decode(value),
+        SentinelDecoding::Signed => value as i16 as f32 / 10.0,
+    }
+}
+
+/// Byte order of the 16-bit register values on the wire.
+///
+/// Modbus registers are big-endian by specification. Some gateways swap the two bytes of each register,
+/// use [`ByteOrder::LittleEndian`] to read and write correct values through such a gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    /// Big-endian as specified by Modbus.
+    #[default]
+    BigEndian,
+    /// The bytes of each register are swapped.
+    LittleEndian,
+}
+
+impl ByteOrder {
+    /// Converts a register value between the wire and the big-endian representation used by the decode
+    /// and encode functions. Swapping is symmetric, the same function is used in both directions.
+    pub fn apply(self, value: u16) -> u16 {
+        match self {
+            ByteOrder::BigEndian => value,
+            ByteOrder::LittleEndian => value.swap_bytes(),
         }
     }
 }
@@ -81,10 +492,144 @@ pub const DEGREE_CELSIUS_MAX: f32 = 3276.7;
 pub fn degree_celsius_encode(value: f32) -> std::result::Result<u16, Error> {
     if !(DEGREE_CELSIUS_MIN..=DEGREE_CELSIUS_MAX).contains(&value) {
         Err(Error::DegreeCelsiusOutOfRange(value))
-    } else if value >= 0.0 {
-        Ok((value * 10.0) as u16)
     } else {
-        Ok((65536.0 + value * 10.0) as u16)
+        // Round to tenths symmetrically, truncating the two's complement `65536.0 + value * 10.0`
+        // would round negative values down, e.g. -0.04 to -0.1 but 0.04 to 0.0
+        Ok((value * 10.0).round() as i16 as u16)
+    }
+}
+
+/// A conservative default range of temperature corrections in °C.
+///
+/// Any temperature can be encoded as correction. This is not a documented limit of the device,
+/// corrections beyond ±10 °C are rather a sign of a faulty sensor or a calibration mistake.
+pub const CORRECTION_MIN: f32 = -10.0;
+pub const CORRECTION_MAX: f32 = 10.0;
+
+/// Returns true if the correction is within [`CORRECTION_MIN`] and [`CORRECTION_MAX`].
+pub fn correction_in_range(correction: f32) -> bool {
+    (CORRECTION_MIN..=CORRECTION_MAX).contains(&correction)
+}
+
+/// Checks that the correction is within the conservative range, see [`correction_in_range`].
+pub fn write_temperature_correction_check_range(correction: f32) -> Result<(), Error> {
+    if correction_in_range(correction) {
+        Ok(())
+    } else {
+        Err(Error::CorrectionOutOfRange(correction))
+    }
+}
+
+/// Returns the temperature correction which makes a channel read the `reference` temperature.
+///
+/// * 'reading' - The current temperature of the channel, already corrected by `correction`.
+/// * 'correction' - The current temperature correction of the channel, NaN is treated as no correction.
+///
+/// The correction is rounded to tenths of a degree as stored by the device.
+pub fn calibration_correction(reading: f32, correction: f32, reference: f32) -> Result<f32, Error> {
+    let correction = if correction.is_nan() { 0.0 } else { correction };
+    Ok(degree_celsius_decode(degree_celsius_encode(
+        correction + reference - reading,
+    )?))
+}
+
+/// Returns the temperature corrections for a calibration table, one `(reference, measured)` pair per channel.
+///
+/// `measured` is the uncorrected reading of the channel, so each correction is `reference - measured`,
+/// rounded to tenths of a degree. Every correction is checked with [`write_temperature_correction_check_range`].
+pub fn calibration_table_corrections(table: &[(f32, f32)]) -> Result<Vec<f32>, Error> {
+    check_channel_count(table.len())?;
+    table
+        .iter()
+        .map(|(reference, measured)| {
+            let correction = calibration_correction(*measured, 0.0, *reference)?;
+            write_temperature_correction_check_range(correction)?;
+            Ok(correction)
+        })
+        .collect()
+}
+
+/// Applies temperature correction values to temperatures in software.
+///
+/// This models the correction the device applies internally, e.g. to preview a correction before writing it.
+/// Each channel is corrected by the value at the same index, channels without a correction stay unchanged.
+/// A NaN temperature or correction results in NaN, the sum saturates at
+/// [`DEGREE_CELSIUS_MIN`] and [`DEGREE_CELSIUS_MAX`].
+pub fn apply_temperature_correction(temperatures: &[f32], corrections: &[f32]) -> Vec<f32> {
+    temperatures
+        .iter()
+        .enumerate()
+        .map(|(channel, temperature)| {
+            let correction = corrections.get(channel).copied().unwrap_or(0.0);
+            if temperature.is_nan() || correction.is_nan() {
+                f32::NAN
+            } else {
+                (temperature + correction).clamp(DEGREE_CELSIUS_MIN, DEGREE_CELSIUS_MAX)
+            }
+        })
+        .collect()
+}
+
+/// Returns the channels with an active temperature correction, i.e. a correction which is neither 0.0 nor NaN.
+pub fn active_correction_channels(corrections: &[f32]) -> Vec<u8> {
+    corrections
+        .iter()
+        .enumerate()
+        .filter(|(_, correction)| !correction.is_nan() && **correction != 0.0)
+        .map(|(channel, _)| channel as u8)
+        .collect()
+}
+
+/// Calculates the Modbus CRC-16 of a RTU frame without the CRC.
+///
+/// The CRC is appended to a frame in little endian byte order, e.g. to build raw frames
+/// or validate frames received from the device.
+pub fn modbus_crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u16, |crc, _| {
+            if crc & 0x0001 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// Decodes a frame pushed by the device when automatic reporting is enabled.
+///
+/// Returns the RS485 address of the device and the temperatures of all channels in °C.
+pub fn automatic_report_decode_frame(frame: &[u8]) -> Result<(u8, Vec<f32>), Error> {
+    if frame.len() != AUTOMATIC_REPORT_FRAME_LEN
+        || frame[1] != AUTOMATIC_REPORT_FUNCTION_CODE
+        || frame[2] as usize != AUTOMATIC_REPORT_FRAME_LEN - 5
+    {
+        return Err(Error::InvalidFrame);
+    }
+    let (data, crc) = frame.split_at(AUTOMATIC_REPORT_FRAME_LEN - 2);
+    let expected = modbus_crc16(data);
+    let actual = u16::from_le_bytes([crc[0], crc[1]]);
+    if expected != actual {
+        return Err(Error::CrcMismatch { expected, actual });
+    }
+    let temperatures = data[3..]
+        .chunks_exact(2)
+        .map(|word| degree_celsius_decode(u16::from_be_bytes([word[0], word[1]])))
+        .collect();
+    Ok((frame[0], temperatures))
+}
+
+/// Converts a temperature in °C into the raw register value as sent by the device.
+///
+/// This is the lossless inverse of [`degree_celsius_decode`] for logging and replaying register contents.
+/// Unlike [`degree_celsius_encode`] this never fails: NaN is mapped to 0x8000 (no sensor or error)
+/// and values outside of the representable range saturate at [`DEGREE_CELSIUS_MIN`] and [`DEGREE_CELSIUS_MAX`].
+pub fn degree_celsius_to_raw(value: f32) -> u16 {
+    if value.is_nan() {
+        0x8000
+    } else {
+        let tenths = (value * 10.0).round().clamp(-32767.0, 32767.0);
+        tenths as i16 as u16
     }
 }
 
@@ -98,11 +643,62 @@ pub fn write_temperature_correction_check_channel(channel: u8) -> std::result::R
     }
 }
 
+/// Checks that `count` values, one per channel beginning at channel 0, fit into the channels of the device.
+pub fn check_channel_count(count: usize) -> std::result::Result<(), Error> {
+    if count <= NUMBER_OF_CHANNELS as usize {
+        Ok(())
+    } else {
+        Err(Error::TooManyChannels(count))
+    }
+}
+
+pub fn read_temperature_range_check(start: u8, count: u8) -> std::result::Result<(), Error> {
+    if count > 0 && start as u16 + count as u16 <= NUMBER_OF_CHANNELS as u16 {
+        Ok(())
+    } else {
+        Err(Error::ChannelRangeOutOfRange(start, count))
+    }
+}
+
 pub const DURATION_MIN: u8 = 0;
 pub const DURATION_MAX: u8 = 255;
 pub fn read_automatic_report_decode_duration(value: u16) -> Duration {
     Duration::from_secs(value as u64)
 }
+
+/// The automatic report register split into the interval (lower byte) and the upper byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutomaticReport {
+    /// Report interval, zero if disabled.
+    pub interval: Duration,
+    /// The upper byte of the register, some firmware is documented to encode mode bits here.
+    pub extra_bits: u8,
+}
+
+/// Decodes the automatic report register for firmware using the upper byte for other purposes.
+///
+/// Unlike [`read_automatic_report_decode_duration`], the upper byte is not part of the interval.
+pub fn read_automatic_report_decode_lenient(value: u16) -> AutomaticReport {
+    let [extra_bits, interval] = value.to_be_bytes();
+    AutomaticReport {
+        interval: Duration::from_secs(interval as u64),
+        extra_bits,
+    }
+}
+
+/// Returns the time by which the next automatic report is expected, `grace` after the `interval`
+/// elapsed since the `last_report` was received.
+///
+/// A device which did not push a report by then stopped reporting, e.g. it lost power.
+/// Returns `None` for the interval 0, automatic reporting is disabled.
+pub fn automatic_report_deadline(
+    last_report: Instant,
+    interval: Duration,
+    grace: Duration,
+) -> Option<Instant> {
+    (!interval.is_zero()).then(|| last_report + interval + grace)
+}
+
 pub fn write_automatic_report_encode_duration(value: Duration) -> Result<u16, Error> {
     if (DURATION_MIN as u64..=DURATION_MAX as u64).contains(&value.as_secs()) {
         Ok(value.as_secs().try_into().unwrap())
@@ -113,18 +709,647 @@ pub fn write_automatic_report_encode_duration(value: Duration) -> Result<u16, Er
 
 pub const ADDRESS_MIN: u8 = 1;
 pub const ADDRESS_MAX: u8 = 247;
+/// Returns true if the address is the broadcast address used to query the address of a single device.
+pub fn is_broadcast_address(address: u8) -> bool {
+    address == READ_ADDRESS_BROADCAST_ADDRESS
+}
+
+/// Returns true if the address can be assigned to a device, see [`ADDRESS_MIN`] and [`ADDRESS_MAX`].
+pub fn is_assignable_address(address: u8) -> bool {
+    (ADDRESS_MIN..=ADDRESS_MAX).contains(&address)
+}
+
+/// Decodes the address register value.
+///
+/// A device never has the broadcast address, if it is reported the device is misconfigured
+/// and [`Error::BroadcastAddressReported`] is returned.
+pub fn read_address_decode(value: u16) -> Result<u8, Error> {
+    match u8::try_from(value) {
+        Ok(address) if is_broadcast_address(address) => {
+            Err(Error::BroadcastAddressReported(address))
+        }
+        Ok(address) => Ok(address),
+        Err(_) => Err(Error::InvalidAddress(value)),
+    }
+}
+
 pub fn write_address_encode_address(address: u8) -> std::result::Result<u16, Error> {
-    if (ADDRESS_MIN..=ADDRESS_MAX).contains(&address) {
+    if is_broadcast_address(address) {
+        Err(Error::InvalidAddressAssignment(address))
+    } else if is_assignable_address(address) {
         Ok(address as u16)
     } else {
         Err(Error::AddressOutOfRange(address))
     }
 }
 
+/// State of a temperature correction channel after a failed batch write, see `set_temperature_correction_all_atomic`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrectionWriteState {
+    /// The write failed, the channel is assumed unchanged.
+    Failed,
+    /// The new value was written and the original value was written back.
+    RolledBack,
+    /// The new value was written, but writing back the original value failed.
+    RollbackFailed,
+    /// The channel was not written.
+    Unchanged,
+}
+
+/// Configuration to commission a device, see [`DeviceConfig::steps`] for the order the values are applied.
+///
+/// Empty or `None` values are left unchanged on the device.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceConfig {
+    /// Temperature correction values in °C per channel.
+    pub temperature_corrections: Vec<(u8, f32)>,
+    pub automatic_report: Option<Duration>,
+    pub address: Option<u8>,
+    pub baud_rate: Option<BaudRate>,
+}
+
+/// A single value applied while commissioning a device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommissionStep {
+    TemperatureCorrection { channel: u8, correction: f32 },
+    AutomaticReport(Duration),
+    Address(u8),
+    BaudRate(BaudRate),
+}
+
+impl DeviceConfig {
+    /// Checks all values, so an invalid value is detected before anything is written to the device.
+    pub fn check(&self) -> Result<(), Error> {
+        for (channel, correction) in &self.temperature_corrections {
+            write_temperature_correction_check_channel(*channel)?;
+            degree_celsius_encode(*correction)?;
+        }
+        if let Some(report) = self.automatic_report {
+            write_automatic_report_encode_duration(report)?;
+        }
+        if let Some(address) = self.address {
+            write_address_encode_address(address)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the values to apply in order.
+    ///
+    /// The temperature corrections and the automatic report are written first using the current address.
+    /// The address is changed afterwards, further requests must use the new address.
+    /// The baud rate is written last, because it is only applied when the device is powered up again.
+    pub fn steps(&self) -> Vec<CommissionStep> {
+        let mut steps = self
+            .temperature_corrections
+            .iter()
+            .map(
+                |(channel, correction)| CommissionStep::TemperatureCorrection {
+                    channel: *channel,
+                    correction: *correction,
+                },
+            )
+            .collect::<Vec<_>>();
+        steps.extend(self.automatic_report.map(CommissionStep::AutomaticReport));
+        steps.extend(self.address.map(CommissionStep::Address));
+        steps.extend(self.baud_rate.map(CommissionStep::BaudRate));
+        steps
+    }
+}
+
+/// The values applied while commissioning a device.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommissionReport {
+    pub applied: Vec<CommissionStep>,
+}
+
+impl CommissionReport {
+    /// Returns true if the device must be powered up again to apply the configuration.
+    pub fn power_cycle_required(&self) -> bool {
+        self.applied
+            .iter()
+            .any(|step| matches!(step, CommissionStep::BaudRate(_)))
+    }
+}
+
+/// The configuration of the device stored in the automatic report, address and baud rate registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigBlock {
+    pub automatic_report: Duration,
+    pub address: u8,
+    pub baud_rate: BaudRate,
+}
+
+/// Decodes the words read from the automatic report, address and baud rate registers.
+pub fn read_config_block_decode(words: &[u16]) -> Result<ConfigBlock, Error> {
+    let [automatic_report, address, baud_rate] = words else {
+        return Err(Error::RegisterCountMismatch {
+            expected: READ_CONFIG_BLOCK_REG_QUAN,
+            actual: words.len(),
+        });
+    };
+    Ok(ConfigBlock {
+        automatic_report: read_automatic_report_decode_duration(*automatic_report),
+        address: read_address_decode(*address)?,
+        baud_rate: BaudRate::try_decode(*baud_rate)?,
+    })
+}
+
+/// The framing of a Modbus message on the wire, this is also the transport of a client connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Modbus/RTU: slave address and CRC around the PDU.
+    Rtu,
+    /// Modbus/TCP: MBAP header in front of the PDU.
+    Tcp,
+    /// Modbus ASCII: ':', slave address, PDU and LRC as hex characters and CR LF.
+    Ascii,
+}
+
+impl Framing {
+    /// Returns the length in bytes of the ADU (the whole message) containing a PDU of `pdu_len` bytes.
+    pub fn adu_len(&self, pdu_len: usize) -> usize {
+        match self {
+            // slave address + PDU + CRC
+            Framing::Rtu => 1 + pdu_len + 2,
+            // MBAP header (transaction id, protocol id, length, unit id) + PDU
+            Framing::Tcp => 7 + pdu_len,
+            // ':' + hex encoded slave address, PDU and LRC + CR LF
+            Framing::Ascii => 1 + 2 * (1 + pdu_len + 1) + 2,
+        }
+    }
+}
+
+/// Calculates the Modbus LRC of an ASCII frame, the two's complement of the sum of the address and PDU bytes.
+pub fn modbus_lrc(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(0u8, |lrc, byte| lrc.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+/// Encodes a Modbus RTU frame of the PDU sent to or received from the device at `address`.
+pub fn rtu_encode_frame(address: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(Framing::Rtu.adu_len(pdu.len()));
+    frame.push(address);
+    frame.extend_from_slice(pdu);
+    let crc = modbus_crc16(&frame);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    frame
+}
+
+/// Encodes a Modbus ASCII frame of the PDU sent to or received from the device at `address`.
+pub fn ascii_encode_frame(address: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + pdu.len() + 1);
+    data.push(address);
+    data.extend_from_slice(pdu);
+    data.push(modbus_lrc(&data));
+    let mut frame = Vec::with_capacity(Framing::Ascii.adu_len(pdu.len()));
+    frame.push(b':');
+    for byte in data {
+        frame.extend_from_slice(format!("{byte:02X}").as_bytes());
+    }
+    frame.extend_from_slice(b"\r\n");
+    frame
+}
+
+/// Decodes a Modbus ASCII frame including the leading ':' and the trailing CR LF.
+///
+/// Returns the address of the device and the PDU.
+pub fn ascii_decode_frame(frame: &[u8]) -> Result<(u8, Vec<u8>), Error> {
+    let hex = frame
+        .strip_prefix(b":")
+        .and_then(|frame| frame.strip_suffix(b"\r\n"))
+        .filter(|hex| hex.len() >= 6 && hex.len() % 2 == 0)
+        .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+        .ok_or(Error::InvalidAsciiFrame)?;
+    // only hex digits are left, so the conversion cannot fail
+    let digit = |digit: u8| (digit as char).to_digit(16).unwrap_or_default() as u8;
+    let data = hex
+        .chunks_exact(2)
+        .map(|pair| digit(pair[0]) << 4 | digit(pair[1]))
+        .collect::<Vec<_>>();
+    let (lrc, data) = data.split_last().ok_or(Error::InvalidAsciiFrame)?;
+    let expected = modbus_lrc(data);
+    if expected != *lrc {
+        return Err(Error::LrcMismatch {
+            expected,
+            actual: *lrc,
+        });
+    }
+    Ok((data[0], data[1..].to_vec()))
+}
+
+/// Function code, start address and quantity of a read holding registers request.
+const READ_REQUEST_PDU_LEN: usize = 1 + 2 + 2;
+
+/// Function code, byte count and the register values of a read holding registers response.
+fn read_response_pdu_len(quantity: u16) -> usize {
+    1 + 1 + 2 * quantity as usize
+}
+
+/// The readable holding registers of the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    Temperature,
+    TemperatureCorrection,
+    AutomaticReport,
+    Address,
+    BaudRate,
+}
+
+impl Register {
+    /// The start address of the register with the default [`RegisterLayout`].
+    pub fn address(&self) -> u16 {
+        match self {
+            Register::Temperature => READ_TEMPERATURE_REG_ADDR,
+            Register::TemperatureCorrection => READ_TEMPERATURE_CORRECTION_REG_ADDR,
+            Register::AutomaticReport => READ_AUTOMATIC_REPORT_REG_ADDR,
+            Register::Address => READ_ADDRESS_REG_ADDR,
+            Register::BaudRate => READ_BAUD_RATE_REG_ADDR,
+        }
+    }
+
+    /// The number of bytes of the request reading the register.
+    pub fn request_bytes(&self, framing: Framing) -> usize {
+        framing.adu_len(READ_REQUEST_PDU_LEN)
+    }
+
+    /// The number of bytes of the response to reading the register.
+    pub fn response_bytes(&self, framing: Framing) -> usize {
+        framing.adu_len(read_response_pdu_len(self.quantity()))
+    }
+
+    /// The number of 16 bit words to read.
+    pub fn quantity(&self) -> u16 {
+        match self {
+            Register::Temperature => READ_TEMPERATURE_REG_QUAN,
+            Register::TemperatureCorrection => READ_TEMPERATURE_CORRECTION_REG_QUAN,
+            Register::AutomaticReport => READ_AUTOMATIC_REPORT_REG_QUAN,
+            Register::Address => READ_ADDRESS_REG_QUAN,
+            Register::BaudRate => READ_BAUD_RATE_REG_QUAN,
+        }
+    }
+}
+
+/// Access of a register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterAccess {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl RegisterAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegisterAccess::Read => "R",
+            RegisterAccess::Write => "W",
+            RegisterAccess::ReadWrite => "R/W",
+        }
+    }
+}
+
+/// Description of a register of the device, see [`REGISTER_MAP`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterInfo {
+    pub name: &'static str,
+    pub address: u16,
+    pub quantity: u16,
+    pub access: RegisterAccess,
+    pub description: &'static str,
+}
+
+/// All registers of the device with the default [`RegisterLayout`].
+pub const REGISTER_MAP: &[RegisterInfo] = &[
+    RegisterInfo {
+        name: "Temperature",
+        address: READ_TEMPERATURE_REG_ADDR,
+        quantity: READ_TEMPERATURE_REG_QUAN,
+        access: RegisterAccess::Read,
+        description: "Temperature per channel in 0.1 °C, 0x8000 means no sensor or error",
+    },
+    RegisterInfo {
+        name: "Temperature correction",
+        address: READ_TEMPERATURE_CORRECTION_REG_ADDR,
+        quantity: READ_TEMPERATURE_CORRECTION_REG_QUAN,
+        access: RegisterAccess::ReadWrite,
+        description: "Correction added to the temperature per channel in 0.1 °C",
+    },
+    RegisterInfo {
+        name: "Automatic report",
+        address: READ_AUTOMATIC_REPORT_REG_ADDR,
+        quantity: READ_AUTOMATIC_REPORT_REG_QUAN,
+        access: RegisterAccess::ReadWrite,
+        description:
+            "Interval of the automatic temperature report in seconds, 0 disables the report",
+    },
+    RegisterInfo {
+        name: "Address",
+        address: READ_ADDRESS_REG_ADDR,
+        quantity: READ_ADDRESS_REG_QUAN,
+        access: RegisterAccess::ReadWrite,
+        description:
+            "RS485 address from 1 to 247, read it with the broadcast address 255 if unknown",
+    },
+    RegisterInfo {
+        name: "Baud rate",
+        address: READ_BAUD_RATE_REG_ADDR,
+        quantity: READ_BAUD_RATE_REG_QUAN,
+        access: RegisterAccess::ReadWrite,
+        description: "0: 1200, 1: 2400, 2: 4800, 3: 9600, 4: 19200, applied after a power cycle",
+    },
+    RegisterInfo {
+        name: "Factory reset",
+        address: WRITE_FACTORY_RESET_REG_ADDR,
+        quantity: 1,
+        access: RegisterAccess::Write,
+        description: "Writing 5 to the baud rate register resets the factory default settings",
+    },
+];
+
+/// Formats the [`REGISTER_MAP`] as a markdown table.
+pub fn register_map_markdown() -> String {
+    let mut markdown = String::from(
+        "| Name | Address | Quantity | Access | Description |\n| --- | --- | --- | --- | --- |\n",
+    );
+    for info in REGISTER_MAP {
+        markdown.push_str(&format!(
+            "| {} | {:#06X} | {} | {} | {} |\n",
+            info.name,
+            info.address,
+            info.quantity,
+            info.access.as_str(),
+            info.description
+        ));
+    }
+    markdown
+}
+
+/// Formats the [`REGISTER_MAP`] as CSV, fields containing a comma are quoted.
+pub fn register_map_csv() -> String {
+    let quote = |field: &str| {
+        if field.contains([',', '"']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+    let mut csv = String::from("name,address,quantity,access,description\n");
+    for info in REGISTER_MAP {
+        csv.push_str(&format!(
+            "{},{:#06X},{},{},{}\n",
+            quote(info.name),
+            info.address,
+            info.quantity,
+            info.access.as_str(),
+            quote(info.description)
+        ));
+    }
+    csv
+}
+
+/// A decoded register value, see [`decode_any`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedValue {
+    Temperature(Vec<f32>),
+    TemperatureCorrection(Vec<f32>),
+    AutomaticReport(Duration),
+    Address(u8),
+    BaudRate(BaudRate),
+}
+
+/// Decodes the words read from a register into the matching type.
+///
+/// This is the single entry point to all register decoders and never panics on invalid input,
+/// which makes it suitable for fuzzing.
+pub fn decode_any(register: Register, words: &[u16]) -> Result<DecodedValue, Error> {
+    if words.len() != register.quantity() as usize {
+        return Err(Error::RegisterCountMismatch {
+            expected: register.quantity(),
+            actual: words.len(),
+        });
+    }
+    Ok(match register {
+        Register::Temperature => DecodedValue::Temperature(
+            words
+                .iter()
+                .map(|value| degree_celsius_decode(*value))
+                .collect(),
+        ),
+        Register::TemperatureCorrection => DecodedValue::TemperatureCorrection(
+            words
+                .iter()
+                .map(|value| degree_celsius_decode(*value))
+                .collect(),
+        ),
+        Register::AutomaticReport => {
+            DecodedValue::AutomaticReport(read_automatic_report_decode_duration(words[0]))
+        }
+        Register::Address => DecodedValue::Address(read_address_decode(words[0])?),
+        Register::BaudRate => DecodedValue::BaudRate(BaudRate::try_decode(words[0])?),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn address_register() {
+        // The address register is the same for the broadcast and a specific address
+        assert_eq!(READ_ADDRESS_REG_ADDR, 0x00FE);
+        assert_eq!(WRITE_ADDRESS_REG_ADDR, 0x00FE);
+        assert_eq!(RegisterLayout::default().address_reg_addr, 0x00FE);
+        assert_eq!(Register::Address.address(), 0x00FE);
+        assert_eq!(Register::Address.quantity(), 1);
+    }
+
+    #[test]
+    fn register_layout_validate() {
+        assert!(RegisterLayout::default().validate().is_ok());
+        assert!(RegisterLayout {
+            temperature_reg_addr: 0x1000,
+            temperature_correction_reg_addr: 0x1008,
+            ..Default::default()
+        }
+        .validate()
+        .is_ok());
+        assert!(matches!(
+            RegisterLayout {
+                temperature_correction_reg_addr: 0x0007,
+                ..Default::default()
+            }
+            .validate(),
+            Err(Error::InvalidRegisterLayout(_))
+        ));
+        assert!(matches!(
+            RegisterLayout {
+                temperature_reg_addr: 0xFFF9,
+                ..Default::default()
+            }
+            .validate(),
+            Err(Error::InvalidRegisterLayout(_))
+        ));
+        assert!(matches!(
+            RegisterLayout {
+                address_reg_addr: 0x00FD,
+                ..Default::default()
+            }
+            .validate(),
+            Err(Error::InvalidRegisterLayout(_))
+        ));
+    }
+
+    #[test]
+    fn config_block() {
+        assert!(RegisterLayout::default().config_block_contiguous());
+        assert!(!RegisterLayout {
+            address_reg_addr: 0x0100,
+            ..Default::default()
+        }
+        .config_block_contiguous());
+
+        assert_eq!(
+            read_config_block_decode(&[10, 1, 3]).unwrap(),
+            ConfigBlock {
+                automatic_report: Duration::from_secs(10),
+                address: 1,
+                baud_rate: BaudRate::B9600,
+            }
+        );
+        assert!(matches!(
+            read_config_block_decode(&[10, 1]),
+            Err(Error::RegisterCountMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            read_config_block_decode(&[10, 0x100, 3]),
+            Err(Error::InvalidAddress(0x100))
+        ));
+        assert!(matches!(
+            read_config_block_decode(&[10, 1, 5]),
+            Err(Error::InvalidBaudRate(5))
+        ));
+    }
+
+    #[test]
+    fn estimate_read_transaction_duration_test() {
+        assert_eq!(
+            estimate_read_transaction_duration(9600, READ_TEMPERATURE_REG_QUAN),
+            Duration::from_micros(41_250)
+        );
+        assert_eq!(
+            estimate_read_transaction_duration(19200, 1),
+            Duration::from_nanos(12_604_166)
+        );
+        assert!(
+            estimate_read_transaction_duration(1200, 8)
+                > estimate_read_transaction_duration(9600, 8)
+        );
+    }
+
+    #[test]
+    fn register_bytes() {
+        assert_eq!(Register::Temperature.request_bytes(Framing::Rtu), 8);
+        assert_eq!(Register::Temperature.response_bytes(Framing::Rtu), 21);
+        assert_eq!(Register::Temperature.request_bytes(Framing::Tcp), 12);
+        assert_eq!(Register::Temperature.response_bytes(Framing::Tcp), 25);
+        assert_eq!(Register::BaudRate.request_bytes(Framing::Rtu), 8);
+        assert_eq!(Register::BaudRate.response_bytes(Framing::Rtu), 7);
+        assert_eq!(Register::BaudRate.response_bytes(Framing::Tcp), 11);
+        // the automatic report frame is a response to reading the temperatures
+        assert_eq!(
+            Register::Temperature.response_bytes(Framing::Rtu),
+            AUTOMATIC_REPORT_FRAME_LEN
+        );
+    }
+
+    #[test]
+    fn register_map() {
+        for register in [
+            Register::Temperature,
+            Register::TemperatureCorrection,
+            Register::AutomaticReport,
+            Register::Address,
+            Register::BaudRate,
+        ] {
+            assert!(REGISTER_MAP
+                .iter()
+                .any(|info| info.address == register.address()
+                    && info.quantity == register.quantity()
+                    && info.access != RegisterAccess::Write));
+        }
+
+        let markdown = register_map_markdown();
+        assert_eq!(markdown.lines().count(), REGISTER_MAP.len() + 2);
+        assert!(markdown.contains("| Temperature | 0x0000 | 8 | R |"));
+
+        let csv = register_map_csv();
+        assert_eq!(csv.lines().count(), REGISTER_MAP.len() + 1);
+        assert!(csv.contains(
+            "Temperature,0x0000,8,R,\"Temperature per channel in 0.1 °C, 0x8000 means no sensor or error\"\n"
+        ));
+    }
+
+    #[test]
+    fn channel_set() {
+        let set: ChannelSet = "5, 0,2,5".parse().unwrap();
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(0) && set.contains(2) && set.contains(5));
+        assert!(!set.contains(1) && !set.contains(8));
+        assert_eq!(set.to_string(), "0,2,5");
+        assert_eq!(set.to_string().parse::<ChannelSet>().unwrap(), set);
+
+        let empty: ChannelSet = "".parse().unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.to_string(), "");
+        assert_eq!(ChannelSet::ALL.to_string(), "0,1,2,3,4,5,6,7");
+
+        assert!(matches!(
+            "1,8".parse::<ChannelSet>(),
+            Err(Error::ChannelOutOfRange(8))
+        ));
+        assert!(matches!(
+            "1,x".parse::<ChannelSet>(),
+            Err(Error::InvalidChannel(channel)) if channel == "x"
+        ));
+        assert!(matches!(
+            "-1".parse::<ChannelSet>(),
+            Err(Error::InvalidChannel(..))
+        ));
+    }
+
+    #[test]
+    fn baud_rate() {
+        for baud_rate in BaudRate::ALL {
+            assert_eq!(BaudRate::try_decode(baud_rate.encode()).unwrap(), baud_rate);
+        }
+        assert_eq!(
+            BaudRate::ALL.map(|baud_rate| baud_rate.bits_per_second()),
+            [1200, 2400, 4800, 9600, 19200]
+        );
+        assert!(matches!(
+            BaudRate::try_decode(5),
+            Err(Error::InvalidBaudRate(5))
+        ));
+    }
+
+    #[test]
+    fn byte_order() {
+        assert_eq!(ByteOrder::default(), ByteOrder::BigEndian);
+        assert_eq!(ByteOrder::BigEndian.apply(0x00DB), 0x00DB);
+        assert_eq!(ByteOrder::LittleEndian.apply(0xDB00), 0x00DB);
+        assert_eq!(
+            degree_celsius_decode(ByteOrder::LittleEndian.apply(0xDB00)),
+            21.9
+        );
+        assert_eq!(
+            ByteOrder::LittleEndian.apply(ByteOrder::LittleEndian.apply(0x1234)),
+            0x1234
+        );
+    }
+
     #[test]
     fn degree_celsius() {
         assert_eq!(degree_celsius_decode(219), 21.9);
@@ -158,6 +1383,362 @@ mod tests {
         assert!(degree_celsius_decode(32768).is_nan());
     }
 
+    #[test]
+    fn degree_celsius_decode_with_test() {
+        assert!(degree_celsius_decode_with(0x8000, SentinelDecoding::Nan).is_nan());
+        assert_eq!(
+            degree_celsius_decode_with(0x8000, SentinelDecoding::Signed),
+            -3276.8
+        );
+        for sentinel in [SentinelDecoding::Nan, SentinelDecoding::Signed] {
+            assert_eq!(degree_celsius_decode_with(219, sentinel), 21.9);
+            assert_eq!(degree_celsius_decode_with(65424, sentinel), -11.2);
+            assert_eq!(degree_celsius_decode_with(32769, sentinel), -3276.7);
+        }
+        assert!(matches!(
+            degree_celsius_encode(-3276.8),
+            Err(Error::DegreeCelsiusOutOfRange(..))
+        ));
+    }
+
+    #[test]
+    fn degree_celsius_to_raw_test() {
+        for word in 0..=u16::MAX {
+            assert_eq!(degree_celsius_to_raw(degree_celsius_decode(word)), word);
+        }
+        assert_eq!(degree_celsius_to_raw(f32::NAN), 0x8000);
+        assert_eq!(degree_celsius_to_raw(5000.0), 0x7FFF);
+        assert_eq!(degree_celsius_to_raw(-5000.0), 0x8001);
+        assert_eq!(degree_celsius_to_raw(f32::INFINITY), 0x7FFF);
+    }
+
+    #[test]
+    fn device_config() {
+        let config = DeviceConfig {
+            temperature_corrections: vec![(0, 1.5), (7, -0.5)],
+            automatic_report: Some(Duration::from_secs(10)),
+            address: Some(2),
+            baud_rate: Some(BaudRate::B19200),
+        };
+        assert!(config.check().is_ok());
+        assert_eq!(
+            config.steps(),
+            vec![
+                CommissionStep::TemperatureCorrection {
+                    channel: 0,
+                    correction: 1.5
+                },
+                CommissionStep::TemperatureCorrection {
+                    channel: 7,
+                    correction: -0.5
+                },
+                CommissionStep::AutomaticReport(Duration::from_secs(10)),
+                CommissionStep::Address(2),
+                CommissionStep::BaudRate(BaudRate::B19200),
+            ]
+        );
+        assert!(DeviceConfig::default().steps().is_empty());
+
+        assert!(matches!(
+            DeviceConfig {
+                temperature_corrections: vec![(8, 0.0)],
+                ..Default::default()
+            }
+            .check(),
+            Err(Error::ChannelOutOfRange(8))
+        ));
+        assert!(matches!(
+            DeviceConfig {
+                address: Some(0xFF),
+                ..Default::default()
+            }
+            .check(),
+            Err(Error::InvalidAddressAssignment(0xFF))
+        ));
+
+        let mut report = CommissionReport {
+            applied: vec![CommissionStep::Address(2)],
+        };
+        assert!(!report.power_cycle_required());
+        report
+            .applied
+            .push(CommissionStep::BaudRate(BaudRate::B9600));
+        assert!(report.power_cycle_required());
+    }
+
+    #[test]
+    fn channel_status() {
+        assert_eq!(channel_status_decode(219), ChannelStatus::Ok(21.9));
+        assert_eq!(channel_status_decode(65424), ChannelStatus::Ok(-11.2));
+        assert_eq!(channel_status_decode(0x8000), ChannelStatus::NoSensor);
+    }
+
+    #[test]
+    fn degree_celsius_round_trip() {
+        assert!(matches!(degree_celsius_encode(-0.1), Ok(0xFFFF)));
+        assert_eq!(degree_celsius_decode(0xFFFF), -0.1);
+        assert!(matches!(degree_celsius_encode(-3276.7), Ok(0x8001)));
+        assert!(matches!(degree_celsius_encode(-0.0), Ok(0)));
+
+        // values between tenths are rounded symmetrically
+        assert!(matches!(degree_celsius_encode(0.04), Ok(0)));
+        assert!(matches!(degree_celsius_encode(-0.04), Ok(0)));
+        assert!(matches!(degree_celsius_encode(0.15), Ok(2)));
+        assert!(matches!(degree_celsius_encode(-0.15), Ok(0xFFFE)));
+
+        // every value of the two's complement range except the sentinel 0x8000
+        for tenths in -32767..=32767i16 {
+            let value = tenths as f32 / 10.0;
+            let encoded = degree_celsius_encode(value).unwrap();
+            assert_eq!(encoded, tenths as u16, "{value}");
+            assert_eq!(degree_celsius_decode(encoded), value, "{value}");
+        }
+    }
+
+    #[test]
+    fn degree_celsius_decode_tenths_test() {
+        assert_eq!(degree_celsius_decode_tenths(0x00DB), Some(219));
+        assert_eq!(degree_celsius_decode_tenths(0xFF90), Some(-112));
+        assert_eq!(degree_celsius_decode_tenths(0x8001), Some(-32767));
+        assert_eq!(degree_celsius_decode_tenths(0x8000), None);
+    }
+
+    #[test]
+    fn degree_celsius_encode_tenths_test() {
+        assert_eq!(degree_celsius_encode_tenths(219), 0x00DB);
+        assert_eq!(degree_celsius_encode_tenths(-1), 0xFFFF);
+        assert_eq!(degree_celsius_encode_tenths(i16::MIN), 0x8000);
+        for tenths in -32767..=32767i16 {
+            let encoded = degree_celsius_encode_tenths(tenths);
+            assert_eq!(degree_celsius_decode(encoded), tenths as f32 / 10.0);
+            assert_eq!(encoded, degree_celsius_to_raw(tenths as f32 / 10.0));
+        }
+    }
+
+    #[test]
+    fn degree_celsius_from_tenths_test() {
+        assert_eq!(degree_celsius_from_tenths(219), 21.9);
+        assert_eq!(degree_celsius_from_tenths(-112), -11.2);
+        assert_eq!(degree_celsius_from_tenths(0), 0.0);
+        assert_eq!(degree_celsius_from_tenths(i16::MAX), 3276.7);
+        assert_eq!(degree_celsius_from_tenths(i16::MIN + 1), -3276.7);
+        assert!(degree_celsius_from_tenths(i16::MIN).is_nan());
+    }
+
+    #[test]
+    fn apply_temperature_correction_test() {
+        let corrected = apply_temperature_correction(
+            &[21.5, -3.0, f32::NAN, 10.0, 3276.0],
+            &[0.5, -1.0, 1.0, f32::NAN, 1.0],
+        );
+        crate::assert_temperatures_eq!(
+            corrected,
+            [22.0, -4.0, f32::NAN, f32::NAN, DEGREE_CELSIUS_MAX],
+            0.0
+        );
+
+        assert_eq!(
+            apply_temperature_correction(&[1.0, 2.0], &[1.0]),
+            [2.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn active_correction_channels_test() {
+        assert_eq!(
+            active_correction_channels(&[0.0, 0.5, f32::NAN, -1.2, 0.0, 0.0, -0.0, 0.1]),
+            [1, 3, 7]
+        );
+        assert!(active_correction_channels(&[0.0; 8]).is_empty());
+    }
+
+    #[test]
+    fn ascii_frame() {
+        // read 8 holding registers from 0x0000 of device 0x01
+        let pdu = [0x03, 0x00, 0x00, 0x00, 0x08];
+        let frame = ascii_encode_frame(0x01, &pdu);
+        assert_eq!(frame, b":010300000008F4\r\n");
+        assert_eq!(frame.len(), Framing::Ascii.adu_len(pdu.len()));
+        assert_eq!(ascii_decode_frame(&frame).unwrap(), (0x01, pdu.to_vec()));
+        assert_eq!(
+            ascii_decode_frame(b":010300000008f4\r\n").unwrap(),
+            (0x01, pdu.to_vec())
+        );
+
+        assert!(matches!(
+            ascii_decode_frame(b":010300000008F5\r\n"),
+            Err(Error::LrcMismatch {
+                expected: 0xF4,
+                actual: 0xF5
+            })
+        ));
+        for invalid in [
+            &b"010300000008F4\r\n"[..],
+            b":010300000008F4",
+            b":010300000008F\r\n",
+            b":01+300000008F4\r\n",
+            b":01F4\r\n",
+        ] {
+            assert!(matches!(
+                ascii_decode_frame(invalid),
+                Err(Error::InvalidAsciiFrame)
+            ));
+        }
+    }
+
+    #[test]
+    fn automatic_report_lenient() {
+        assert_eq!(
+            read_automatic_report_decode_lenient(0x0A),
+            AutomaticReport {
+                interval: Duration::from_secs(10),
+                extra_bits: 0,
+            }
+        );
+        assert_eq!(
+            read_automatic_report_decode_lenient(0x810A),
+            AutomaticReport {
+                interval: Duration::from_secs(10),
+                extra_bits: 0x81,
+            }
+        );
+        assert_eq!(
+            read_automatic_report_decode_duration(0x810A),
+            Duration::from_secs(0x810A)
+        );
+    }
+
+    #[test]
+    fn calibration_correction_test() {
+        assert_eq!(calibration_correction(21.9, 0.0, 21.5).unwrap(), -0.4);
+        assert_eq!(calibration_correction(21.9, -0.4, 22.0).unwrap(), -0.3);
+        assert_eq!(calibration_correction(20.0, f32::NAN, 20.5).unwrap(), 0.5);
+        assert!(matches!(
+            calibration_correction(-3000.0, 0.0, 3000.0),
+            Err(Error::DegreeCelsiusOutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn modbus_crc16_test() {
+        assert_eq!(modbus_crc16(&[]), 0xFFFF);
+        assert_eq!(modbus_crc16(b"123456789"), 0x4B37);
+        // read holding register 0x0000 of device 0x01, sent as 0x84 0x0A
+        assert_eq!(
+            modbus_crc16(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]).to_le_bytes(),
+            [0x84, 0x0A]
+        );
+    }
+
+    #[test]
+    fn automatic_report_decode_frame_test() {
+        let mut frame = vec![0x01, 0x03, 0x10];
+        for word in [219u16, 65424, 32768, 0, 100, 65506, 32767, 32769] {
+            frame.extend_from_slice(&word.to_be_bytes());
+        }
+        frame.extend_from_slice(&modbus_crc16(&frame).to_le_bytes());
+
+        let (address, temperatures) = automatic_report_decode_frame(&frame).unwrap();
+        assert_eq!(address, 0x01);
+        crate::assert_temperatures_eq!(
+            temperatures,
+            [21.9, -11.2, f32::NAN, 0.0, 10.0, -3.0, 3276.7, -3276.7],
+            0.0
+        );
+
+        let mut corrupted = frame.clone();
+        corrupted[4] ^= 0x01;
+        assert!(matches!(
+            automatic_report_decode_frame(&corrupted),
+            Err(Error::CrcMismatch { .. })
+        ));
+        assert!(matches!(
+            automatic_report_decode_frame(&frame[1..]),
+            Err(Error::InvalidFrame)
+        ));
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        assert_eq!(
+            diagnostics_encode_request(DIAGNOSTICS_RETURN_BUS_MESSAGE_COUNT),
+            [0x00, 0x0B, 0x00, 0x00]
+        );
+        assert_eq!(
+            diagnostics_decode_response(
+                DIAGNOSTICS_RETURN_BUS_MESSAGE_COUNT,
+                &[0x00, 0x0B, 0x01, 0x02]
+            ),
+            Some(0x0102)
+        );
+        assert_eq!(
+            diagnostics_decode_response(
+                DIAGNOSTICS_RETURN_BUS_COMMUNICATION_ERROR_COUNT,
+                &[0x00, 0x0B, 0x01, 0x02]
+            ),
+            None
+        );
+        assert_eq!(
+            diagnostics_decode_response(DIAGNOSTICS_RETURN_BUS_MESSAGE_COUNT, &[0x00, 0x0B]),
+            None
+        );
+    }
+
+    #[test]
+    fn decode_any_test() {
+        assert!(matches!(
+            decode_any(Register::Temperature, &[219, 65424, 32768, 0, 0, 0, 0, 0]),
+            Ok(DecodedValue::Temperature(values)) if values[0] == 21.9 && values[1] == -11.2 && values[2].is_nan()
+        ));
+        assert!(matches!(
+            decode_any(Register::Temperature, &[219]),
+            Err(Error::RegisterCountMismatch {
+                expected: 8,
+                actual: 1
+            })
+        ));
+        assert_eq!(
+            decode_any(Register::AutomaticReport, &[10]).unwrap(),
+            DecodedValue::AutomaticReport(Duration::from_secs(10))
+        );
+        assert_eq!(
+            decode_any(Register::Address, &[0x01]).unwrap(),
+            DecodedValue::Address(0x01)
+        );
+        assert!(matches!(
+            decode_any(Register::Address, &[0x0100]),
+            Err(Error::InvalidAddress(0x0100))
+        ));
+        assert!(matches!(
+            decode_any(Register::Address, &[0x00FF]),
+            Err(Error::BroadcastAddressReported(0xFF))
+        ));
+        assert_eq!(
+            decode_any(Register::BaudRate, &[3]).unwrap(),
+            DecodedValue::BaudRate(BaudRate::B9600)
+        );
+        assert!(matches!(
+            decode_any(Register::BaudRate, &[5]),
+            Err(Error::InvalidBaudRate(5))
+        ));
+        assert!(matches!(
+            decode_any(Register::BaudRate, &[]),
+            Err(Error::RegisterCountMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn address_test() {
+        assert!(is_broadcast_address(0xFF));
+        assert!(!is_broadcast_address(FACTORY_DEFAULT_ADDRESS));
+
+        assert!(!is_assignable_address(0));
+        assert!(is_assignable_address(1));
+        assert!(is_assignable_address(247));
+        assert!(!is_assignable_address(248));
+        assert!(!is_assignable_address(READ_ADDRESS_BROADCAST_ADDRESS));
+    }
+
     #[test]
     fn write_address_encode_address_test() {
         assert!(matches!(
@@ -170,6 +1751,10 @@ mod tests {
             write_address_encode_address(248),
             Err(Error::AddressOutOfRange(..))
         ));
+        assert!(matches!(
+            write_address_encode_address(READ_ADDRESS_BROADCAST_ADDRESS),
+            Err(Error::InvalidAddressAssignment(0xFF))
+        ));
     }
 
     #[test]
@@ -188,6 +1773,39 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn check_channel_count_test() {
+        assert!(matches!(check_channel_count(0), Ok(())));
+        assert!(matches!(check_channel_count(8), Ok(())));
+        assert!(matches!(
+            check_channel_count(9),
+            Err(Error::TooManyChannels(9))
+        ));
+        assert!(matches!(
+            check_channel_count(256),
+            Err(Error::TooManyChannels(256))
+        ));
+    }
+
+    #[test]
+    fn read_temperature_range_check_test() {
+        assert!(matches!(read_temperature_range_check(0, 8), Ok(())));
+        assert!(matches!(read_temperature_range_check(0, 1), Ok(())));
+        assert!(matches!(read_temperature_range_check(7, 1), Ok(())));
+        assert!(matches!(
+            read_temperature_range_check(0, 0),
+            Err(Error::ChannelRangeOutOfRange(0, 0))
+        ));
+        assert!(matches!(
+            read_temperature_range_check(4, 5),
+            Err(Error::ChannelRangeOutOfRange(4, 5))
+        ));
+        assert!(matches!(
+            read_temperature_range_check(255, 255),
+            Err(Error::ChannelRangeOutOfRange(..))
+        ));
+    }
+
     #[test]
     fn write_automatic_report_encode_duration_test() {
         assert_eq!(read_automatic_report_decode_duration(0), Duration::ZERO);
@@ -221,4 +1839,94 @@ mod tests {
             Err(Error::DurationOutOfRange(..))
         ));
     }
+
+    #[test]
+    fn temperatures_view() {
+        let words = [0x00DB, 0xFF90, 0x8000];
+        let view = TemperaturesView::new(&words);
+        assert_eq!(view.len(), 3);
+        assert!(!view.is_empty());
+        assert_eq!(view.get(0), Some(21.9));
+        assert_eq!(view.get(1), Some(-11.2));
+        assert!(view.get(2).unwrap().is_nan());
+        assert_eq!(view.get(3), None);
+        assert_eq!(view.raw(), &words);
+        assert_eq!(view.iter().take(2).collect::<Vec<_>>(), [21.9, -11.2]);
+        assert!(TemperaturesView::new(&[]).is_empty());
+
+        // registers of a byte swapping gateway
+        let words = [0xDB00, 0x90FF, 0x0080];
+        let view = TemperaturesView::new(&words).with_byte_order(ByteOrder::LittleEndian);
+        assert_eq!(view.get(0), Some(21.9));
+        assert_eq!(view.get(1), Some(-11.2));
+        assert!(view.get(2).unwrap().is_nan());
+        assert_eq!(view.raw(), &words);
+        let view = view.with_sentinel(SentinelDecoding::Signed);
+        assert_eq!(view.iter().collect::<Vec<_>>(), [21.9, -11.2, -3276.8]);
+    }
+
+    #[test]
+    fn read_address_decode() {
+        assert_eq!(super::read_address_decode(0x0001).unwrap(), 0x01);
+        assert_eq!(super::read_address_decode(0x00F7).unwrap(), 0xF7);
+        assert!(matches!(
+            super::read_address_decode(0x00FF),
+            Err(Error::BroadcastAddressReported(0xFF))
+        ));
+        assert!(matches!(
+            super::read_address_decode(0x0100),
+            Err(Error::InvalidAddress(0x0100))
+        ));
+    }
+
+    #[test]
+    fn rtu_encode_frame() {
+        assert_eq!(
+            super::rtu_encode_frame(0x01, &[0x03, 0x00, 0x00, 0x00, 0x02]),
+            [0x01, 0x03, 0x00, 0x00, 0x00, 0x02, 0xC4, 0x0B]
+        );
+    }
+
+    #[test]
+    fn calibration_table_corrections() {
+        assert_eq!(
+            super::calibration_table_corrections(&[(21.5, 21.9), (20.0, 19.25)]).unwrap(),
+            [-0.4, 0.8]
+        );
+        assert!(super::calibration_table_corrections(&[])
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            super::calibration_table_corrections(&[(20.0, 20.0), (35.0, 20.0)]),
+            Err(Error::CorrectionOutOfRange(_))
+        ));
+        assert!(matches!(
+            super::calibration_table_corrections(&[(20.0, 20.0); 9]),
+            Err(Error::TooManyChannels(9))
+        ));
+    }
+
+    #[test]
+    fn write_temperature_correction_check_range() {
+        assert!(super::write_temperature_correction_check_range(-10.0).is_ok());
+        assert!(super::write_temperature_correction_check_range(10.0).is_ok());
+        assert!(matches!(
+            super::write_temperature_correction_check_range(10.1),
+            Err(Error::CorrectionOutOfRange(_))
+        ));
+        assert!(!correction_in_range(f32::NAN));
+    }
+
+    #[test]
+    fn automatic_report_deadline() {
+        let now = Instant::now();
+        assert_eq!(
+            super::automatic_report_deadline(now, Duration::from_secs(10), Duration::from_secs(2)),
+            Some(now + Duration::from_secs(12))
+        );
+        assert_eq!(
+            super::automatic_report_deadline(now, Duration::ZERO, Duration::from_secs(2)),
+            None
+        );
+    }
 }
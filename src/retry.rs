@@ -0,0 +1,208 @@
+use crate::tokio_error::Error;
+use std::time::Duration;
+use tokio_modbus::ExceptionCode;
+
+/// Policy to retry failed operations, e.g. on a noisy RS485 bus.
+///
+/// Transport errors (e.g. timeouts) and the configured Modbus exceptions are retried,
+/// all other errors are returned immediately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts including the first one.
+    pub max_attempts: u32,
+    /// Delay between two attempts.
+    pub delay: Duration,
+    /// Modbus exceptions which are retried, by default "Acknowledge" (0x05) and "Server Device Busy" (0x06).
+    ///
+    /// E.g. add [`ExceptionCode::ServerDeviceFailure`] (0x04) for devices failing transiently under EMI.
+    pub retryable_exceptions: Vec<ExceptionCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            delay: Duration::from_millis(50),
+            retryable_exceptions: vec![ExceptionCode::Acknowledge, ExceptionCode::ServerDeviceBusy],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns true if the operation failing with this error should be attempted again.
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match error {
+            Error::ModbusError(tokio_modbus::Error::Transport(_)) => true,
+            Error::ModbusException(exception) => self.retryable_exceptions.contains(exception),
+            _ => false,
+        }
+    }
+
+    /// Records the error of a failed attempt.
+    ///
+    /// Returns the error to give up with, or `None` if the operation should be attempted again.
+    fn record_failure(&self, attempts: &mut Vec<Error>, error: Error) -> Option<Error> {
+        if !self.is_retryable(&error) {
+            return Some(error);
+        }
+        attempts.push(error);
+        if attempts.len() >= self.max_attempts as usize {
+            Some(Error::RetriesExhausted {
+                attempts: std::mem::take(attempts),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Runs the operation until it succeeds or the attempts are exhausted, blocking the thread between attempts.
+    ///
+    /// If all attempts fail, [`Error::RetriesExhausted`] contains the errors of all attempts.
+    /// E.g. `policy.retry(|| client.read_temperature())`
+    #[cfg(any(feature = "tokio-rtu-sync", feature = "tokio-tcp-sync"))]
+    pub fn retry<T>(&self, mut operation: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+        let mut attempts = Vec::new();
+        loop {
+            match operation() {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    if let Some(error) = self.record_failure(&mut attempts, error) {
+                        return Err(error);
+                    }
+                }
+            }
+            std::thread::sleep(self.delay);
+        }
+    }
+
+    /// Runs the operation until it succeeds or the attempts are exhausted, see [`Self::retry`].
+    ///
+    /// The delay between attempts does not block the executor.
+    #[cfg(any(feature = "tokio-rtu", feature = "tokio-tcp"))]
+    pub async fn retry_async<T, F>(&self, mut operation: impl FnMut() -> F) -> Result<T, Error>
+    where
+        F: std::future::Future<Output = Result<T, Error>>,
+    {
+        let mut attempts = Vec::new();
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    if let Some(error) = self.record_failure(&mut attempts, error) {
+                        return Err(error);
+                    }
+                }
+            }
+            tokio::time::sleep(self.delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout() -> Error {
+        tokio_modbus::Error::Transport(std::io::ErrorKind::TimedOut.into()).into()
+    }
+
+    #[cfg(any(feature = "tokio-rtu-sync", feature = "tokio-tcp-sync"))]
+    #[test]
+    fn retry_test() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            delay: Duration::ZERO,
+            ..Default::default()
+        };
+
+        let mut calls = 0;
+        let result = policy.retry(|| {
+            calls += 1;
+            if calls < 3 {
+                Err(timeout())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert!(matches!(result, Ok(3)));
+
+        let mut calls = 0;
+        let result: Result<(), _> = policy.retry(|| {
+            calls += 1;
+            Err(timeout())
+        });
+        assert_eq!(calls, 3);
+        assert!(matches!(result, Err(Error::RetriesExhausted { attempts }) if attempts.len() == 3));
+
+        let mut calls = 0;
+        let result: Result<(), _> = policy.retry(|| {
+            calls += 1;
+            Err(tokio_modbus::ExceptionCode::IllegalDataAddress.into())
+        });
+        assert_eq!(calls, 1);
+        assert!(matches!(result, Err(Error::ModbusException(..))));
+    }
+
+    #[test]
+    fn retryable_exceptions() {
+        let mut policy = RetryPolicy::default();
+        assert!(policy.is_retryable(&ExceptionCode::ServerDeviceBusy.into()));
+        assert!(policy.is_retryable(&ExceptionCode::Acknowledge.into()));
+        assert!(!policy.is_retryable(&ExceptionCode::ServerDeviceFailure.into()));
+        assert!(!policy.is_retryable(&crate::Error::ChannelOutOfRange(8).into()));
+
+        policy
+            .retryable_exceptions
+            .push(ExceptionCode::ServerDeviceFailure);
+        assert!(policy.is_retryable(&ExceptionCode::ServerDeviceFailure.into()));
+        #[cfg(any(feature = "tokio-rtu-sync", feature = "tokio-tcp-sync"))]
+        {
+            policy.delay = Duration::ZERO;
+            let mut calls = 0;
+            let result = policy.retry(|| {
+                calls += 1;
+                if calls < 2 {
+                    Err(ExceptionCode::ServerDeviceFailure.into())
+                } else {
+                    Ok(calls)
+                }
+            });
+            assert!(matches!(result, Ok(2)));
+        }
+    }
+
+    #[cfg(any(feature = "tokio-rtu", feature = "tokio-tcp"))]
+    #[test]
+    fn retry_async() {
+        let policy = RetryPolicy {
+            delay: Duration::ZERO,
+            ..Default::default()
+        };
+        let calls = std::cell::Cell::new(0);
+        let result = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(policy.retry_async(|| async {
+                calls.set(calls.get() + 1);
+                if calls.get() < 3 {
+                    Err(ExceptionCode::ServerDeviceBusy.into())
+                } else {
+                    Ok(calls.get())
+                }
+            }));
+        assert!(matches!(result, Ok(3)));
+
+        calls.set(0);
+        let result: Result<(), _> = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(policy.retry_async(|| async {
+                calls.set(calls.get() + 1);
+                Err(timeout())
+            }));
+        assert_eq!(calls.get(), 3);
+        assert!(matches!(result, Err(Error::RetriesExhausted { attempts }) if attempts.len() == 3));
+    }
+}
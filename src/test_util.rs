@@ -0,0 +1,83 @@
+use std::fmt::Write;
+
+/// Asserts that two slices of temperatures are equal channel by channel.
+///
+/// NaN equals NaN and values within `epsilon` °C are equal. On failure all channels are listed,
+/// differing channels are marked with `!`.
+#[macro_export]
+macro_rules! assert_temperatures_eq {
+    ($actual:expr, $expected:expr, $epsilon:expr $(,)?) => {
+        if let Some(diff) = $crate::test_util::temperatures_diff(&$actual, &$expected, $epsilon) {
+            panic!("temperatures are not equal:\n{}", diff);
+        }
+    };
+}
+
+fn temperature_eq(actual: f32, expected: f32, epsilon: f32) -> bool {
+    (actual.is_nan() && expected.is_nan()) || (actual - expected).abs() <= epsilon
+}
+
+/// Compares temperatures like [`assert_temperatures_eq`] and returns a readable diff if they are not equal.
+pub fn temperatures_diff(actual: &[f32], expected: &[f32], epsilon: f32) -> Option<String> {
+    let equal = actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(expected)
+            .all(|(actual, expected)| temperature_eq(*actual, *expected, epsilon));
+    if equal {
+        return None;
+    }
+    let mut diff = String::new();
+    if actual.len() != expected.len() {
+        let _ = writeln!(
+            diff,
+            "  {} channels, {} expected",
+            actual.len(),
+            expected.len()
+        );
+    }
+    for channel in 0..actual.len().max(expected.len()) {
+        let (actual, expected) = (actual.get(channel), expected.get(channel));
+        let marker = match (actual, expected) {
+            (Some(actual), Some(expected)) if temperature_eq(*actual, *expected, epsilon) => ' ',
+            _ => '!',
+        };
+        let format = |value: Option<&f32>| value.map_or("-".to_string(), ToString::to_string);
+        let _ = writeln!(
+            diff,
+            "{} channel {}: {} expected {}",
+            marker,
+            channel,
+            format(actual),
+            format(expected)
+        );
+    }
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperatures_diff_test() {
+        assert_eq!(
+            temperatures_diff(&[21.9, f32::NAN], &[21.95, f32::NAN], 0.1),
+            None
+        );
+        assert_eq!(
+            temperatures_diff(&[21.9, f32::NAN], &[21.9, 0.0], 0.1).unwrap(),
+            "  channel 0: 21.9 expected 21.9\n! channel 1: NaN expected 0\n"
+        );
+        assert_eq!(
+            temperatures_diff(&[1.0], &[1.0, 2.0], 0.0).unwrap(),
+            "  1 channels, 2 expected\n  channel 0: 1 expected 1\n! channel 1: - expected 2\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "temperatures are not equal")]
+    fn assert_temperatures_eq_panics() {
+        assert_temperatures_eq!([1.0], [2.0], 0.1);
+    }
+}
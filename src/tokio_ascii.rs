@@ -0,0 +1,197 @@
+use crate::{
+    protocol as proto,
+    tokio_async_client::R4DCB08,
+    tokio_pdu::{decode_response, encode_request, invalid_data},
+};
+use std::{fmt::Debug, io};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio_modbus::{
+    prelude::{Client, Request, Response, SlaveContext},
+    Slave,
+};
+
+type Result<T> = std::result::Result<T, crate::tokio_error::Error>;
+
+/// Modbus ASCII client for legacy gateways, tokio-modbus only supports RTU and TCP.
+///
+/// The requests of [`R4DCB08`] (read holding registers, write single register and custom function codes)
+/// are sent as Modbus ASCII frames over `stream`, e.g. a serial port.
+/// There is no timeout, a missing response waits forever, so wrap calls with `tokio::time::timeout`.
+#[derive(Debug)]
+pub struct AsciiClient<T> {
+    stream: BufReader<T>,
+    slave: Slave,
+}
+
+impl<T> AsciiClient<T> {
+    /// Constructs a new client for the device at `slave`.
+    pub fn new(stream: T, slave: Slave) -> Self
+    where
+        T: AsyncRead,
+    {
+        Self {
+            stream: BufReader::new(stream),
+            slave,
+        }
+    }
+}
+
+impl<T> SlaveContext for AsciiClient<T> {
+    fn set_slave(&mut self, slave: Slave) {
+        self.slave = slave;
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> Client for AsciiClient<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + Debug,
+{
+    async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+        let frame = proto::ascii_encode_frame(self.slave.into(), &encode_request(&request)?);
+        self.stream.get_mut().write_all(&frame).await?;
+        self.stream.get_mut().flush().await?;
+
+        let mut frame = Vec::new();
+        self.stream.read_until(b'\n', &mut frame).await?;
+        // ignore noise on the bus in front of the frame
+        let start = frame.iter().position(|byte| *byte == b':').unwrap_or(0);
+        let (address, pdu) = proto::ascii_decode_frame(&frame[start..]).map_err(invalid_data)?;
+        if address != u8::from(self.slave) {
+            return Err(invalid_data(crate::Error::UnexpectedResponse(
+                request.function_code().value(),
+            )));
+        }
+        decode_response(&request, &pdu)
+    }
+
+    async fn disconnect(&mut self) -> io::Result<()> {
+        self.stream.get_mut().shutdown().await
+    }
+}
+
+/// Opens the serial port `device` and connects to the device at `slave` using Modbus ASCII.
+///
+/// Modbus ASCII commonly uses 7 data bits and even parity, configure the builder to match the gateway.
+/// The path of the builder is replaced by `device`, which is named in the errors of opening the port,
+/// see [`crate::tokio_serial::open_error`].
+/// Must be called within a tokio runtime.
+pub fn connect_slave(
+    device: &str,
+    builder: &tokio_serial::SerialPortBuilder,
+    slave: Slave,
+) -> Result<R4DCB08> {
+    let port = tokio_serial::SerialStream::open(&builder.clone().path(device))
+        .map_err(|error| crate::tokio_serial::open_error(device, error.into()))?;
+    let mut d = R4DCB08::new(tokio_modbus::client::Context::from(
+        Box::new(AsciiClient::new(port, slave)) as Box<dyn Client>,
+    ));
+    d.set_transport(proto::Framing::Ascii);
+    d.set_slave_address(slave.into());
+    Ok(d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio_modbus::prelude::ExceptionCode;
+
+    /// Answers a single request on `stream` with `response` after checking the request.
+    async fn device(
+        mut stream: tokio::io::DuplexStream,
+        request: &'static [u8],
+        response: Vec<u8>,
+    ) {
+        let mut received = vec![0; request.len()];
+        stream.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, request);
+        stream.write_all(&response).await.unwrap();
+    }
+
+    #[test]
+    fn ascii_client() {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let (stream, device_stream) = tokio::io::duplex(64);
+                let mut d = R4DCB08::new(tokio_modbus::client::Context::from(Box::new(
+                    AsciiClient::new(stream, Slave(0x01)),
+                )
+                    as Box<dyn Client>));
+                // temperature 21.9 °C in channel 0 and -11.2 °C in channel 1
+                let mut response = vec![0x03, 0x04, 0x00, 0xDB, 0xFF, 0x90];
+                let task = tokio::spawn(device(
+                    device_stream,
+                    b":010300000002FA\r\n",
+                    proto::ascii_encode_frame(0x01, &response),
+                ));
+                let rsp = d.read_temperature_range(0, 2).await;
+                task.await.unwrap();
+                assert_eq!(rsp.unwrap(), [21.9, -11.2]);
+
+                let (stream, device_stream) = tokio::io::duplex(64);
+                let mut client = AsciiClient::new(stream, Slave(0x01));
+                response[1] = 0x02;
+                // byte count does not match the register values
+                let task = tokio::spawn(device(
+                    device_stream,
+                    b":010300000002FA\r\n",
+                    proto::ascii_encode_frame(0x01, &response),
+                ));
+                let rsp = client.call(Request::ReadHoldingRegisters(0, 2)).await;
+                task.await.unwrap();
+                assert!(rsp.is_err());
+
+                let (stream, device_stream) = tokio::io::duplex(64);
+                let mut client = AsciiClient::new(stream, Slave(0x01));
+                let task = tokio::spawn(device(
+                    device_stream,
+                    b":01060008000AE7\r\n",
+                    b":01860277\r\n".to_vec(),
+                ));
+                let rsp = client.call(Request::WriteSingleRegister(0x08, 10)).await;
+                task.await.unwrap();
+                assert_eq!(rsp.unwrap(), Err(ExceptionCode::IllegalDataAddress));
+
+                let (stream, device_stream) = tokio::io::duplex(64);
+                let mut client = AsciiClient::new(stream, Slave(0x01));
+                let task = tokio::spawn(device(
+                    device_stream,
+                    b":0117000000020008000102000AD1\r\n",
+                    proto::ascii_encode_frame(0x01, &[0x17, 0x04, 0x00, 0xDB, 0xFF, 0x90]),
+                ));
+                let rsp = client
+                    .call(Request::ReadWriteMultipleRegisters(
+                        0,
+                        2,
+                        0x08,
+                        std::borrow::Cow::Borrowed(&[10]),
+                    ))
+                    .await;
+                task.await.unwrap();
+                assert_eq!(
+                    rsp.unwrap(),
+                    Ok(Response::ReadWriteMultipleRegisters(vec![0x00DB, 0xFF90]))
+                );
+            });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn connect_slave_not_found() {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let builder = tokio_serial::new("", 9600);
+                assert!(matches!(
+                    super::connect_slave("/dev/tempcol-missing", &builder, Slave(0x01)),
+                    Err(crate::tokio_error::Error::SerialPortNotFound(device))
+                        if device == "/dev/tempcol-missing"
+                ));
+            });
+    }
+}
@@ -1,17 +1,163 @@
 use crate::protocol as proto;
-use std::time::Duration;
-use tokio_modbus::prelude::{Reader, Writer};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::Poll,
+    time::Duration,
+};
+use tokio_modbus::prelude::{
+    Client, ExceptionCode, Reader, Request, Response, SlaveContext, Writer,
+};
 
 type Result<T> = std::result::Result<T, crate::tokio_error::Error>;
 
 pub struct R4DCB08 {
     ctx: tokio_modbus::client::Context,
+    layout: proto::RegisterLayout,
+    correction_sentinel: proto::SentinelDecoding,
+    byte_order: proto::ByteOrder,
+    transport: Option<proto::Framing>,
+    ignore_automatic_report_timeout: bool,
+    strict_correction_range: bool,
+    slave_address: Option<u8>,
+    close_guard: crate::close_guard::CloseGuard,
 }
 
 impl R4DCB08 {
     /// Constructs a new R4DCB08 client
     pub fn new(ctx: tokio_modbus::client::Context) -> Self {
-        Self { ctx }
+        Self {
+            ctx,
+            layout: proto::RegisterLayout::default(),
+            correction_sentinel: proto::SentinelDecoding::default(),
+            byte_order: proto::ByteOrder::default(),
+            transport: None,
+            ignore_automatic_report_timeout: false,
+            strict_correction_range: false,
+            slave_address: None,
+            close_guard: Default::default(),
+        }
+    }
+
+    /// Constructs a new R4DCB08 client using a custom register layout
+    ///
+    /// Fails if the layout is invalid, see [`proto::RegisterLayout::validate`].
+    pub fn with_layout(
+        ctx: tokio_modbus::client::Context,
+        layout: proto::RegisterLayout,
+    ) -> Result<Self> {
+        layout.validate()?;
+        let mut d = Self::new(ctx);
+        d.layout = layout;
+        Ok(d)
+    }
+
+    /// Limits the requests of this client to `ops_per_second` on average, e.g. to share a gateway fairly with other clients.
+    ///
+    /// Each request waits for a token of a token bucket holding up to one second of requests (at least one),
+    /// so short bursts are sent without delay. Unlike a delay between requests, this caps the overall throughput.
+    ///
+    /// Fails if `ops_per_second` is not a positive number, see [`crate::Error::InvalidRateLimit`].
+    pub fn with_rate_limit(mut self, ops_per_second: f64) -> Result<Self> {
+        if !(ops_per_second.is_finite() && ops_per_second > 0.0) {
+            return Err(crate::Error::InvalidRateLimit(ops_per_second).into());
+        }
+        let ctx = std::mem::replace(
+            &mut self.ctx,
+            tokio_modbus::client::Context::from(Box::new(NoClient) as Box<dyn Client>),
+        );
+        self.ctx = tokio_modbus::client::Context::from(Box::new(RateLimitedClient {
+            inner: ctx,
+            ops_per_second,
+            tokens: ops_per_second.max(1.0),
+            last_refill: tokio::time::Instant::now(),
+        }) as Box<dyn Client>);
+        Ok(self)
+    }
+
+    /// Returns the register layout used by this client.
+    pub fn layout(&self) -> &proto::RegisterLayout {
+        &self.layout
+    }
+
+    /// Sets how the register value 0x8000 of a temperature correction is decoded.
+    ///
+    /// The default decodes 0x8000 as NaN, see [`proto::SentinelDecoding`].
+    pub fn set_correction_sentinel(&mut self, sentinel: proto::SentinelDecoding) {
+        self.correction_sentinel = sentinel;
+    }
+
+    /// Returns the transport of the connection, `None` if it is unknown.
+    ///
+    /// E.g. to keep the inter-frame delay required by Modbus/RTU only for RTU connections.
+    pub fn transport(&self) -> Option<proto::Framing> {
+        self.transport
+    }
+
+    /// Sets the transport of the connection, clients created by the connect functions of this crate set it.
+    pub fn set_transport(&mut self, transport: proto::Framing) {
+        self.transport = Some(transport);
+    }
+
+    /// Treats a timeout of [`Self::set_automatic_report`] as success, disabled by default.
+    ///
+    /// Some firmware starts pushing reports immediately and does not respond to the write enabling them,
+    /// so the write looks failed although it worked.
+    pub fn set_ignore_automatic_report_timeout(&mut self, ignore: bool) {
+        self.ignore_automatic_report_timeout = ignore;
+    }
+
+    /// Rejects temperature corrections outside of [`proto::CORRECTION_MIN`] to [`proto::CORRECTION_MAX`], disabled by default.
+    ///
+    /// See [`proto::correction_in_range`], the caller should warn about such corrections when disabled.
+    pub fn set_strict_correction_range(&mut self, strict: bool) {
+        self.strict_correction_range = strict;
+    }
+
+    /// Encodes a temperature correction, checking the range if [`Self::set_strict_correction_range`] is enabled.
+    fn encode_correction(&self, correction: f32) -> std::result::Result<u16, crate::Error> {
+        if self.strict_correction_range {
+            proto::write_temperature_correction_check_range(correction)?;
+        }
+        proto::degree_celsius_encode(correction)
+    }
+
+    /// Changes the RS485 address (Modbus slave) of the device the client talks to.
+    ///
+    /// Unlike [`Self::set_address`] nothing is written to the device, e.g. to configure several devices on one bus.
+    pub fn set_slave_address(&mut self, address: u8) {
+        self.ctx.set_slave(tokio_modbus::Slave(address));
+        self.slave_address = Some(address);
+    }
+
+    /// Returns the RS485 address (Modbus slave) of the device the client talks to, e.g. for log messages.
+    ///
+    /// `None` if the address is unknown, because the client was constructed from a context
+    /// which cannot report its address.
+    pub fn slave_address(&self) -> Option<u8> {
+        self.slave_address
+    }
+
+    /// Sets the byte order of the register values, see [`proto::ByteOrder`].
+    ///
+    /// Use [`proto::ByteOrder::LittleEndian`] for gateways swapping the bytes of each register.
+    /// The byte order is applied to all read and written register values.
+    pub fn set_byte_order(&mut self, byte_order: proto::ByteOrder) {
+        self.byte_order = byte_order;
+    }
+
+    /// Closes the connection, e.g. to release a serial port opened with exclusive access.
+    ///
+    /// Dropping the client closes the connection as well, but without reporting errors.
+    /// With the `log` feature dropping a client which was not closed is logged.
+    pub async fn close(mut self) -> Result<()> {
+        self.close_guard.close();
+        self.ctx
+            .disconnect()
+            .await
+            .map_err(|error| tokio_modbus::Error::Transport(error).into())
     }
 
     /// Read the current temperature from all channels in °C.
@@ -19,31 +165,173 @@ impl R4DCB08 {
     ///
     /// The returned temperature is corrected by the temperature correction
     pub async fn read_temperature(&mut self) -> Result<Vec<f32>> {
+        Ok(self.read_temperatures_with_raw().await?.0)
+    }
+
+    /// Read the current temperature from all channels in signed tenths of a degree Celsius.
+    ///
+    /// No float conversion is involved, e.g. for integer-only pipelines. `None` indicates no sensor or an error.
+    pub async fn read_temperatures_tenths(&mut self) -> Result<Vec<Option<i16>>> {
+        let rsp = self
+            .ctx
+            .read_holding_registers(
+                self.layout.temperature_reg_addr,
+                proto::READ_TEMPERATURE_REG_QUAN,
+            )
+            .await??;
+        Ok(rsp
+            .iter()
+            .map(|value| proto::degree_celsius_decode_tenths(self.byte_order.apply(*value)))
+            .collect())
+    }
+
+    /// Read the current temperature from all channels in °C like [`Self::read_temperature`]
+    /// together with the register values as received, e.g. to debug suspicious temperatures.
+    pub async fn read_temperatures_with_raw(&mut self) -> Result<(Vec<f32>, Vec<u16>)> {
+        let rsp = self
+            .ctx
+            .read_holding_registers(
+                self.layout.temperature_reg_addr,
+                proto::READ_TEMPERATURE_REG_QUAN,
+            )
+            .await??;
+        let temperatures = rsp
+            .iter()
+            .map(|value| proto::degree_celsius_decode(self.byte_order.apply(*value)))
+            .collect::<Vec<_>>();
+        Ok((temperatures, rsp))
+    }
+
+    /// Read the current temperature from all channels in °C like [`Self::read_temperature`] without allocating the result.
+    ///
+    /// The register values are copied into `raw`, which can be reused between reads, and the temperatures
+    /// are returned in a fixed size array. Note that tokio-modbus still allocates the response internally.
+    /// Fails with [`crate::Error::RegisterCountMismatch`] if the device does not respond with all channels.
+    pub async fn read_temperatures_into(
+        &mut self,
+        raw: &mut [u16; proto::NUMBER_OF_CHANNELS as usize],
+    ) -> Result<[f32; proto::NUMBER_OF_CHANNELS as usize]> {
+        let rsp = self
+            .ctx
+            .read_holding_registers(
+                self.layout.temperature_reg_addr,
+                proto::READ_TEMPERATURE_REG_QUAN,
+            )
+            .await??;
+        if rsp.len() != raw.len() {
+            return Err(crate::Error::RegisterCountMismatch {
+                expected: proto::READ_TEMPERATURE_REG_QUAN,
+                actual: rsp.len(),
+            }
+            .into());
+        }
+        raw.copy_from_slice(&rsp);
+        Ok(raw.map(|value| proto::degree_celsius_decode(self.byte_order.apply(value))))
+    }
+
+    /// Read the current temperature from all channels with the status of each channel.
+    ///
+    /// Unlike [`Self::read_temperature`] a missing sensor is reported explicitly instead of NaN,
+    /// see [`proto::ChannelStatus`].
+    pub async fn read_temperatures_detailed(&mut self) -> Result<Vec<proto::ChannelStatus>> {
         let rsp = self
             .ctx
             .read_holding_registers(
-                proto::READ_TEMPERATURE_REG_ADDR,
+                self.layout.temperature_reg_addr,
                 proto::READ_TEMPERATURE_REG_QUAN,
             )
             .await??;
         Ok(rsp
             .iter()
-            .map(|value| proto::degree_celsius_decode(*value))
+            .map(|value| proto::channel_status_decode(self.byte_order.apply(*value)))
+            .collect::<Vec<_>>())
+    }
+
+    /// Read the temperature from all channels `samples` times, `spacing` apart, and return the average per channel in °C.
+    ///
+    /// This reduces the jitter of the sensors. The `nan_policy` defines whether a NaN sample of a channel
+    /// makes its average NaN or is skipped, see [`crate::analysis::NanPolicy`]. At least one sample is read.
+    pub async fn read_temperatures_averaged(
+        &mut self,
+        samples: u8,
+        spacing: Duration,
+        nan_policy: crate::analysis::NanPolicy,
+    ) -> Result<Vec<f32>> {
+        let mut readings = Vec::with_capacity(samples as usize);
+        for sample in 0..samples.max(1) {
+            if sample > 0 {
+                tokio::time::sleep(spacing).await;
+            }
+            readings.push(self.read_temperature().await?);
+        }
+        Ok(crate::analysis::average_samples(&readings, nan_policy))
+    }
+
+    /// Read the current temperature of `count` contiguous channels beginning at channel `start` in °C.
+    /// If a channel is not connected or an error is occurred, NaN is returned.
+    ///
+    /// * 'start' - First temperature sensor channel 0 to 7.
+    /// * 'count' - Number of channels to read, `start + count` must not exceed 8.
+    pub async fn read_temperature_range(&mut self, start: u8, count: u8) -> Result<Vec<f32>> {
+        proto::read_temperature_range_check(start, count)?;
+        let rsp = self
+            .ctx
+            .read_holding_registers(
+                self.layout.temperature_reg_addr + start as u16,
+                count as u16,
+            )
+            .await??;
+        Ok(rsp
+            .iter()
+            .map(|value| proto::degree_celsius_decode(self.byte_order.apply(*value)))
             .collect::<Vec<_>>())
     }
 
+    /// Read the current temperature of all connected channels in °C, keyed by channel.
+    ///
+    /// Channels reporting NaN (not connected or error) are not contained in the map.
+    pub async fn read_connected(&mut self) -> Result<BTreeMap<u8, f32>> {
+        Ok(self
+            .read_temperature()
+            .await?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, temperature)| !temperature.is_nan())
+            .map(|(channel, temperature)| (channel as u8, temperature))
+            .collect())
+    }
+
+    /// Read the current temperature from all channels in °C and check them against a plausible range.
+    ///
+    /// Temperatures outside of `min..=max` are replaced by NaN, the validity per channel is returned
+    /// alongside. See [`crate::analysis::mark_implausible`].
+    pub async fn read_temperature_plausible(
+        &mut self,
+        min: f32,
+        max: f32,
+    ) -> Result<(Vec<f32>, Vec<crate::analysis::ChannelValidity>)> {
+        let mut temperatures = self.read_temperature().await?;
+        let validity = crate::analysis::mark_implausible(&mut temperatures, min, max);
+        Ok((temperatures, validity))
+    }
+
     /// Read the current temperature correction values form all channels in °C.
     pub async fn read_temperature_correction(&mut self) -> Result<Vec<f32>> {
         let rsp = self
             .ctx
             .read_holding_registers(
-                proto::READ_TEMPERATURE_CORRECTION_REG_ADDR,
+                self.layout.temperature_correction_reg_addr,
                 proto::READ_TEMPERATURE_CORRECTION_REG_QUAN,
             )
             .await??;
         Ok(rsp
             .iter()
-            .map(|value| proto::degree_celsius_decode(*value))
+            .map(|value| {
+                proto::degree_celsius_decode_with(
+                    self.byte_order.apply(*value),
+                    self.correction_sentinel,
+                )
+            })
             .collect::<Vec<_>>())
     }
 
@@ -56,29 +344,215 @@ impl R4DCB08 {
     /// Setting it to 0.0 disables this feature.
     ///
     /// * 'channel' - Temperature sensore channel 0 to 7.
-    /// * 'correction' - Correction value in °Celsius
+    /// * 'correction' - Correction value in °Celsius, see [`Self::set_strict_correction_range`].
     pub async fn set_temperature_correction(&mut self, channel: u8, correction: f32) -> Result<()> {
         proto::write_temperature_correction_check_channel(channel)?;
         Ok(self
             .ctx
             .write_single_register(
-                proto::WRITE_TEMPERATURE_CORRECTION_REG_ADDR + channel as u16,
-                proto::degree_celsius_encode(correction)?,
+                self.layout.temperature_correction_reg_addr + channel as u16,
+                self.byte_order.apply(self.encode_correction(correction)?),
             )
             .await??)
     }
 
+    /// Sets the temperature correction of a channel so it reads the `reference` temperature, e.g. of a reference thermometer.
+    ///
+    /// The current temperature and correction are read first, see [`proto::calibration_correction`].
+    /// Returns the new correction value in °Celsius.
+    pub async fn calibrate_channel_to(&mut self, channel: u8, reference: f32) -> Result<f32> {
+        proto::write_temperature_correction_check_channel(channel)?;
+        let reading = self.read_temperature().await?[channel as usize];
+        if reading.is_nan() {
+            return Err(crate::Error::ChannelDisconnected(channel).into());
+        }
+        let correction = self.read_temperature_correction().await?[channel as usize];
+        let correction = proto::calibration_correction(reading, correction, reference)?;
+        self.set_temperature_correction(channel, correction).await?;
+        Ok(correction)
+    }
+
+    /// Set the temperature corrections of the channels `0..table.len()` from a calibration table
+    /// and read back the temperatures of all channels in °C.
+    ///
+    /// Each correction is `reference - measured`, see [`proto::calibration_table_corrections`].
+    /// All corrections are checked to be within ±10 °C before anything is written,
+    /// they are written with [`Self::write_corrections_and_read_temperatures`].
+    ///
+    /// * 'table' - `(reference, measured)` pairs in °Celsius per channel, at most 8. `measured` is the uncorrected reading.
+    pub async fn apply_calibration_table(&mut self, table: &[(f32, f32)]) -> Result<Vec<f32>> {
+        let corrections = proto::calibration_table_corrections(table)?;
+        self.write_corrections_and_read_temperatures(&corrections)
+            .await
+    }
+
+    /// Set the temperature correction values of the channels `0..corrections.len()` and read back
+    /// the temperatures of all channels in °C, e.g. to verify a calibration.
+    ///
+    /// Both is done in one Read/Write Multiple Registers (0x17) transaction. If the device rejects
+    /// this function code, the corrections are written one by one with rollback on failure like
+    /// [`Self::set_temperature_correction_all_atomic`] and the temperatures are read afterwards.
+    ///
+    /// * 'corrections' - Correction values in °Celsius, at most 8.
+    pub async fn write_corrections_and_read_temperatures(
+        &mut self,
+        corrections: &[f32],
+    ) -> Result<Vec<f32>> {
+        proto::check_channel_count(corrections.len())?;
+        if corrections.is_empty() {
+            return self.read_temperature().await;
+        }
+        let encoded = corrections
+            .iter()
+            .map(|correction| {
+                self.encode_correction(*correction)
+                    .map(|value| self.byte_order.apply(value))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let rsp = self
+            .ctx
+            .read_write_multiple_registers(
+                self.layout.temperature_reg_addr,
+                proto::READ_TEMPERATURE_REG_QUAN,
+                self.layout.temperature_correction_reg_addr,
+                &encoded,
+            )
+            .await?;
+        let rsp = match rsp {
+            Ok(rsp) => rsp,
+            Err(ExceptionCode::IllegalFunction) => {
+                self.set_temperature_correction_all_atomic(corrections)
+                    .await?;
+                self.ctx
+                    .read_holding_registers(
+                        self.layout.temperature_reg_addr,
+                        proto::READ_TEMPERATURE_REG_QUAN,
+                    )
+                    .await??
+            }
+            Err(exception) => return Err(exception.into()),
+        };
+        Ok(rsp
+            .iter()
+            .map(|value| proto::degree_celsius_decode(self.byte_order.apply(*value)))
+            .collect())
+    }
+
+    /// Set the temperature correction values of the channels `0..corrections.len()` with rollback on failure.
+    ///
+    /// The values are written one by one with single register writes. The original values are read first,
+    /// if a write fails the already written channels are set back to their original values (best-effort).
+    /// The state of each channel is returned in [`crate::tokio_error::Error::CorrectionWriteFailed`].
+    ///
+    /// * 'corrections' - Correction values in °Celsius, at most 8.
+    pub async fn set_temperature_correction_all_atomic(
+        &mut self,
+        corrections: &[f32],
+    ) -> Result<()> {
+        proto::check_channel_count(corrections.len())?;
+        let encoded = corrections
+            .iter()
+            .map(|correction| self.encode_correction(*correction))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        // The original values are written back as read, without decoding
+        let originals = self
+            .ctx
+            .read_holding_registers(
+                self.layout.temperature_correction_reg_addr,
+                corrections.len() as u16,
+            )
+            .await??;
+        for (channel, value) in encoded.iter().enumerate() {
+            let written = self
+                .ctx
+                .write_single_register(
+                    self.layout.temperature_correction_reg_addr + channel as u16,
+                    self.byte_order.apply(*value),
+                )
+                .await
+                .map_err(crate::tokio_error::Error::from)
+                .and_then(|rsp| rsp.map_err(crate::tokio_error::Error::from));
+            if let Err(error) = written {
+                let mut states = vec![proto::CorrectionWriteState::Unchanged; encoded.len()];
+                states[channel] = proto::CorrectionWriteState::Failed;
+                for (rollback, original) in originals.iter().enumerate().take(channel) {
+                    let rolled_back = matches!(
+                        self.ctx
+                            .write_single_register(
+                                self.layout.temperature_correction_reg_addr + rollback as u16,
+                                *original,
+                            )
+                            .await,
+                        Ok(Ok(()))
+                    );
+                    states[rollback] = if rolled_back {
+                        proto::CorrectionWriteState::RolledBack
+                    } else {
+                        proto::CorrectionWriteState::RollbackFailed
+                    };
+                }
+                return Err(crate::tokio_error::Error::CorrectionWriteFailed {
+                    error: Box::new(error),
+                    states,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the temperature correction value per channel, but only if a sensor is connected.
+    ///
+    /// The current temperature of the channel is read first. If the channel reports NaN,
+    /// no sensor is connected and [`crate::Error::ChannelDisconnected`] is returned.
+    /// Use [`Self::set_temperature_correction`] to configure channels before wiring the sensors.
+    ///
+    /// * 'channel' - Temperature sensore channel 0 to 7.
+    /// * 'correction' - Correction value in °Celsius
+    pub async fn set_temperature_correction_if_connected(
+        &mut self,
+        channel: u8,
+        correction: f32,
+    ) -> Result<()> {
+        proto::write_temperature_correction_check_channel(channel)?;
+        let temperatures = self.read_temperature().await?;
+        if temperatures
+            .get(channel as usize)
+            .is_none_or(|temperature| temperature.is_nan())
+        {
+            return Err(crate::Error::ChannelDisconnected(channel).into());
+        }
+        self.set_temperature_correction(channel, correction).await
+    }
+
     /// Read temperature automatic reporting
     pub async fn read_automatic_report(&mut self) -> Result<Duration> {
         let rsp = self
             .ctx
             .read_holding_registers(
-                proto::READ_AUTOMATIC_REPORT_REG_ADDR,
+                self.layout.automatic_report_reg_addr,
                 proto::READ_AUTOMATIC_REPORT_REG_QUAN,
             )
             .await??;
         Ok(proto::read_automatic_report_decode_duration(
-            *rsp.first().expect("Result on success expected"),
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
+        ))
+    }
+
+    /// Read temperature automatic reporting with the upper byte of the register separately.
+    ///
+    /// See [`proto::read_automatic_report_decode_lenient`].
+    pub async fn read_automatic_report_lenient(&mut self) -> Result<proto::AutomaticReport> {
+        let rsp = self
+            .ctx
+            .read_holding_registers(
+                self.layout.automatic_report_reg_addr,
+                proto::READ_AUTOMATIC_REPORT_REG_QUAN,
+            )
+            .await??;
+        Ok(proto::read_automatic_report_decode_lenient(
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
         ))
     }
 
@@ -87,14 +561,26 @@ impl R4DCB08 {
     /// The value is set for all 8 channels at the same time.
     ///
     /// * 'report_in_sec' - Report time in seconds. 0 = disabled (default) or from 1 to 255 seconds.
+    ///
+    /// See [`Self::set_ignore_automatic_report_timeout`] for firmware not responding to this write.
     pub async fn set_automatic_report(&mut self, report: Duration) -> Result<()> {
-        Ok(self
+        let rsp = self
             .ctx
             .write_single_register(
-                proto::WRITE_AUTOMATIC_REPORT_REG_ADDR,
-                proto::write_automatic_report_encode_duration(report)?,
+                self.layout.automatic_report_reg_addr,
+                self.byte_order
+                    .apply(proto::write_automatic_report_encode_duration(report)?),
             )
-            .await??)
+            .await;
+        match rsp {
+            Err(tokio_modbus::Error::Transport(error))
+                if self.ignore_automatic_report_timeout
+                    && error.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                Ok(())
+            }
+            rsp => Ok(rsp??),
+        }
     }
 
     /// Read the current baud rate
@@ -102,13 +588,14 @@ impl R4DCB08 {
         let rsp = self
             .ctx
             .read_holding_registers(
-                proto::READ_BAUD_RATE_REG_ADDR,
+                self.layout.baud_rate_reg_addr,
                 proto::READ_BAUD_RATE_REG_QUAN,
             )
             .await??;
-        Ok(proto::BaudRate::decode(
-            *rsp.first().expect("Result on success expected"),
-        ))
+        Ok(proto::BaudRate::try_decode(
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
+        )?)
     }
 
     /// Set the baud rate.
@@ -117,7 +604,10 @@ impl R4DCB08 {
     pub async fn set_baud_rate(&mut self, baud_rate: proto::BaudRate) -> Result<()> {
         Ok(self
             .ctx
-            .write_single_register(proto::WRITE_BAUD_RATE_REG_ADDR, baud_rate.encode())
+            .write_single_register(
+                self.layout.baud_rate_reg_addr,
+                self.byte_order.apply(baud_rate.encode()),
+            )
             .await??)
     }
 
@@ -126,35 +616,813 @@ impl R4DCB08 {
         Ok(self
             .ctx
             .write_single_register(
-                proto::WRITE_FACTORY_RESET_REG_ADDR,
-                proto::WRITE_FACTORY_RESET_REG_DATA,
+                self.layout.factory_reset_reg_addr,
+                self.byte_order.apply(proto::WRITE_FACTORY_RESET_REG_DATA),
             )
             .await??)
     }
 
     /// Reads the current Modbus address
     ///
-    /// Note: When using this command, only one temperature module can be connected to the RS485 bus,
-    /// more than one will be wrong!
-    /// The connected modbus address must be the broadcast address 255.
+    /// The address register is read from the device the client is connected to, with a known address
+    /// this confirms the address of the device.
+    /// If the address is unknown, connect to the broadcast address 255
+    /// ([`proto::READ_ADDRESS_BROADCAST_ADDRESS`]). In that case only one temperature module can be connected
+    /// to the RS485 bus, more than one will be wrong!
+    /// A device reporting the broadcast address itself is misconfigured, see [`crate::Error::BroadcastAddressReported`].
     pub async fn read_address(&mut self) -> Result<u8> {
         let rsp = self
             .ctx
-            .read_holding_registers(proto::READ_ADDRESS_REG_ADDR, proto::READ_ADDRESS_REG_QUAN)
+            .read_holding_registers(self.layout.address_reg_addr, proto::READ_ADDRESS_REG_QUAN)
             .await??;
-        Ok(*rsp.first().expect("Result on success expected") as u8)
+        Ok(proto::read_address_decode(
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
+        )?)
+    }
+
+    /// Returns true if a device responds at the `candidate` address, e.g. before assigning it with [`Self::set_address`].
+    ///
+    /// The address register is read from `candidate`, any response including a Modbus exception means the
+    /// address is in use, a timeout of the transport or no response within `timeout` means it is free.
+    /// Afterwards the client talks to its own address again. Fails without sending anything if the own
+    /// address is unknown, see [`Self::slave_address`].
+    ///
+    /// The async context has no timeout of its own, so a request without response is abandoned after
+    /// `timeout`. A late response is discarded by the next request, which clears the receive buffer before sending.
+    pub async fn is_address_in_use(&mut self, candidate: u8, timeout: Duration) -> Result<bool> {
+        proto::write_address_encode_address(candidate)?;
+        let address = self
+            .slave_address
+            .ok_or(crate::tokio_error::Error::SlaveAddressUnknown)?;
+        self.ctx.set_slave(tokio_modbus::Slave(candidate));
+        let rsp = tokio::time::timeout(
+            timeout,
+            self.ctx
+                .read_holding_registers(self.layout.address_reg_addr, proto::READ_ADDRESS_REG_QUAN),
+        )
+        .await;
+        self.ctx.set_slave(tokio_modbus::Slave(address));
+        match rsp {
+            Err(_) => Ok(false),
+            Ok(Ok(_)) => Ok(true),
+            Ok(Err(tokio_modbus::Error::Transport(error)))
+                if error.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                Ok(false)
+            }
+            Ok(Err(error)) => Err(error.into()),
+        }
     }
 
     /// Set the Modbus address
     ///
-    /// * 'address' - The address can be from 1 to 247.
+    /// * 'address' - The address can be from 1 to 247, the broadcast address 255 is rejected.
     pub async fn set_address(&mut self, address: u8) -> Result<()> {
         Ok(self
             .ctx
             .write_single_register(
-                proto::WRITE_ADDRESS_REG_ADDR,
-                proto::write_address_encode_address(address)?,
+                self.layout.address_reg_addr,
+                self.byte_order
+                    .apply(proto::write_address_encode_address(address)?),
             )
             .await??)
     }
+
+    /// Reads the automatic report, address and baud rate at once.
+    ///
+    /// The registers are read with a single request if they are contiguous in the register layout,
+    /// which is the case for the R4DCB08. Otherwise each register is read on its own.
+    pub async fn read_config_block(&mut self) -> Result<proto::ConfigBlock> {
+        let words = if self.layout.config_block_contiguous() {
+            self.ctx
+                .read_holding_registers(
+                    self.layout.automatic_report_reg_addr,
+                    proto::READ_CONFIG_BLOCK_REG_QUAN,
+                )
+                .await??
+        } else {
+            let mut words = Vec::with_capacity(proto::READ_CONFIG_BLOCK_REG_QUAN as usize);
+            for reg_addr in [
+                self.layout.automatic_report_reg_addr,
+                self.layout.address_reg_addr,
+                self.layout.baud_rate_reg_addr,
+            ] {
+                words.extend(self.ctx.read_holding_registers(reg_addr, 1).await??);
+            }
+            words
+        };
+        let words = words
+            .iter()
+            .map(|value| self.byte_order.apply(*value))
+            .collect::<Vec<_>>();
+        Ok(proto::read_config_block_decode(&words)?)
+    }
+
+    /// Commissions the device by applying the configuration in the required order, see [`proto::DeviceConfig::steps`].
+    ///
+    /// The configuration is checked before anything is written. The client waits `delay` between the
+    /// requests and switches to the new address once it is written.
+    /// Note: The baud rate will be updated when the module is powered up again,
+    /// see [`proto::CommissionReport::power_cycle_required`].
+    pub async fn commission(
+        &mut self,
+        config: &proto::DeviceConfig,
+        delay: Duration,
+    ) -> Result<proto::CommissionReport> {
+        config.check()?;
+        let mut report = proto::CommissionReport::default();
+        for step in config.steps() {
+            if !report.applied.is_empty() {
+                tokio::time::sleep(delay).await;
+            }
+            match step {
+                proto::CommissionStep::TemperatureCorrection {
+                    channel,
+                    correction,
+                } => self.set_temperature_correction(channel, correction).await?,
+                proto::CommissionStep::AutomaticReport(report) => {
+                    self.set_automatic_report(report).await?
+                }
+                proto::CommissionStep::Address(address) => {
+                    self.set_address(address).await?;
+                    self.set_slave_address(address);
+                }
+                proto::CommissionStep::BaudRate(baud_rate) => self.set_baud_rate(baud_rate).await?,
+            }
+            report.applied.push(step);
+        }
+        Ok(report)
+    }
+
+    /// Reads the device identity using the Modbus function "Report Slave ID" (0x11).
+    ///
+    /// The response format of the R4DCB08 is not documented, the raw response bytes are returned
+    /// (usually the byte count followed by the slave ID, the run indicator status and device specific data).
+    pub async fn report_slave_id(&mut self) -> Result<Vec<u8>> {
+        match self
+            .ctx
+            .call(Request::Custom(
+                proto::REPORT_SLAVE_ID_FUNCTION_CODE,
+                Cow::Borrowed(&[]),
+            ))
+            .await??
+        {
+            Response::Custom(_, data) => Ok(data.to_vec()),
+            _ => Err(crate::Error::UnexpectedResponse(proto::REPORT_SLAVE_ID_FUNCTION_CODE).into()),
+        }
+    }
+
+    /// Reads the diagnostic counters using the Modbus diagnostics function (0x08).
+    ///
+    /// This is best-effort, the firmware of the R4DCB08 is not documented to support this function.
+    /// Sub-functions answered with an exception or an unexpected response are reported as `None`.
+    ///
+    /// Note: The RTU framing of tokio-modbus cannot determine the length of a successful diagnostics response,
+    /// therefore counters can only be read with Modbus/TCP (e.g. a RS485 to TCP gateway).
+    pub async fn read_diagnostics(&mut self) -> Result<proto::Diagnostics> {
+        Ok(proto::Diagnostics {
+            bus_message_count: self
+                .read_diagnostics_counter(proto::DIAGNOSTICS_RETURN_BUS_MESSAGE_COUNT)
+                .await?,
+            bus_communication_error_count: self
+                .read_diagnostics_counter(proto::DIAGNOSTICS_RETURN_BUS_COMMUNICATION_ERROR_COUNT)
+                .await?,
+        })
+    }
+
+    async fn read_diagnostics_counter(&mut self, sub_function: u16) -> Result<Option<u16>> {
+        let request = proto::diagnostics_encode_request(sub_function);
+        match self
+            .ctx
+            .call(Request::Custom(
+                proto::DIAGNOSTICS_FUNCTION_CODE,
+                Cow::Borrowed(&request),
+            ))
+            .await?
+        {
+            Ok(Response::Custom(_, data)) => {
+                Ok(proto::diagnostics_decode_response(sub_function, &data))
+            }
+            Ok(_) => Ok(None),
+            Err(ExceptionCode::IllegalFunction | ExceptionCode::IllegalDataValue) => Ok(None),
+            Err(exception) => Err(exception.into()),
+        }
+    }
+}
+
+/// Handle of the background task polling the temperatures, see [`R4DCB08::start_polling`].
+///
+/// `T` is returned by the task when it is stopped.
+pub struct PollingHandle<T = R4DCB08> {
+    stop: tokio::sync::oneshot::Sender<()>,
+    task: tokio::task::JoinHandle<T>,
+}
+
+impl<T> PollingHandle<T> {
+    /// Returns true while the background task is polling.
+    ///
+    /// The task ends if it is stopped or the receiver of the temperatures is dropped.
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Stops polling and returns the client once the current read is completed.
+    ///
+    /// Returns `None` if the task panicked.
+    pub async fn stop(self) -> Option<T> {
+        // The task may have ended already because the receiver was dropped
+        let _ = self.stop.send(());
+        self.task.await.ok()
+    }
+}
+
+/// Waits for the next tick of `interval`, returns true if stopping was requested before.
+async fn tick_or_stop(
+    interval: &mut tokio::time::Interval,
+    stopped: &mut tokio::sync::oneshot::Receiver<()>,
+) -> bool {
+    poll_fn(|cx| {
+        if Pin::new(&mut *stopped).poll(cx).is_ready() {
+            return Poll::Ready(true);
+        }
+        interval.poll_tick(cx).map(|_| false)
+    })
+    .await
+}
+
+fn polling_interval(interval: Duration) -> tokio::time::Interval {
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    interval
+}
+
+impl R4DCB08 {
+    /// Spawns a tokio task reading the temperatures every `interval` and sending the results to `sink`.
+    ///
+    /// The task owns the client until it is stopped with [`PollingHandle::stop`].
+    /// Read errors are sent to `sink` as well, the task keeps polling.
+    /// Must be called within a tokio runtime.
+    pub fn start_polling(
+        self,
+        interval: Duration,
+        sink: tokio::sync::mpsc::Sender<Result<Vec<f32>>>,
+    ) -> PollingHandle {
+        let (stop, mut stopped) = tokio::sync::oneshot::channel::<()>();
+        let mut client = self;
+        let task = tokio::spawn(async move {
+            let mut interval = polling_interval(interval);
+            while !tick_or_stop(&mut interval, &mut stopped).await {
+                let rsp = client.read_temperature().await;
+                if sink.send(rsp).await.is_err() {
+                    break;
+                }
+            }
+            client
+        });
+        PollingHandle { stop, task }
+    }
+}
+
+/// Event of [`start_polling_reconnecting`].
+#[derive(Debug)]
+pub enum StreamEvent {
+    /// Result of reading the temperatures, Modbus exceptions are passed through.
+    Reading(Result<Vec<f32>>),
+    /// The connection is lost or cannot be established, it is attempted again on the next poll.
+    Reconnecting(crate::tokio_error::Error),
+    /// The connection is established again after [`StreamEvent::Reconnecting`].
+    Reconnected,
+}
+
+/// Spawns a tokio task reading the temperatures every `interval`, connecting with `connect` as required.
+///
+/// On a transport error or [`crate::tokio_error::Error::DeviceDisconnected`]
+/// (e.g. the USB adapter is re-enumerated or the TCP connection is closed)
+/// the client is dropped and a new one is connected on the next poll, so the task survives losing the device.
+/// The connection state is reported to `sink` as [`StreamEvent::Reconnecting`] and [`StreamEvent::Reconnected`].
+/// The task ends if it is stopped or the receiver is dropped and returns the current client if connected.
+/// Must be called within a tokio runtime.
+///
+/// E.g. `start_polling_reconnecting(|| async { Ok(R4DCB08::new(tcp::connect(addr).await?)) }, interval, sink)`
+pub fn start_polling_reconnecting<C, F>(
+    mut connect: C,
+    interval: Duration,
+    sink: tokio::sync::mpsc::Sender<StreamEvent>,
+) -> PollingHandle<Option<R4DCB08>>
+where
+    C: FnMut() -> F + Send + 'static,
+    F: Future<Output = Result<R4DCB08>> + Send,
+{
+    let (stop, mut stopped) = tokio::sync::oneshot::channel::<()>();
+    let task = tokio::spawn(async move {
+        let mut interval = polling_interval(interval);
+        let mut client = None;
+        let mut lost = false;
+        while !tick_or_stop(&mut interval, &mut stopped).await {
+            let mut events = Vec::with_capacity(2);
+            if client.is_none() {
+                match connect().await {
+                    Ok(connected) => {
+                        client = Some(connected);
+                        if std::mem::take(&mut lost) {
+                            events.push(StreamEvent::Reconnected);
+                        }
+                    }
+                    Err(error) => {
+                        lost = true;
+                        events.push(StreamEvent::Reconnecting(error));
+                    }
+                }
+            }
+            if let Some(connected) = client.as_mut() {
+                match connected.read_temperature().await {
+                    Err(
+                        error @ (crate::tokio_error::Error::ModbusError(
+                            tokio_modbus::Error::Transport(_),
+                        )
+                        | crate::tokio_error::Error::DeviceDisconnected(_)),
+                    ) => {
+                        client = None;
+                        lost = true;
+                        events.push(StreamEvent::Reconnecting(error));
+                    }
+                    rsp => events.push(StreamEvent::Reading(rsp)),
+                }
+            }
+            for event in events {
+                if sink.send(event).await.is_err() {
+                    return client;
+                }
+            }
+        }
+        client
+    });
+    PollingHandle { stop, task }
+}
+
+/// Token bucket limiting the requests sent through the inner context, see [`R4DCB08::with_rate_limit`].
+#[derive(Debug)]
+struct RateLimitedClient {
+    inner: tokio_modbus::client::Context,
+    ops_per_second: f64,
+    tokens: f64,
+    last_refill: tokio::time::Instant,
+}
+
+impl RateLimitedClient {
+    /// Waits for a token and takes it.
+    async fn acquire(&mut self) {
+        let now = tokio::time::Instant::now();
+        let refill = (now - self.last_refill).as_secs_f64() * self.ops_per_second;
+        self.tokens = (self.tokens + refill).min(self.ops_per_second.max(1.0));
+        self.last_refill = now;
+        if self.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - self.tokens) / self.ops_per_second);
+            tokio::time::sleep(wait).await;
+            self.tokens = 1.0;
+            self.last_refill = now + wait;
+        }
+        self.tokens -= 1.0;
+    }
+}
+
+impl SlaveContext for RateLimitedClient {
+    fn set_slave(&mut self, slave: tokio_modbus::Slave) {
+        self.inner.set_slave(slave);
+    }
+}
+
+#[async_trait::async_trait]
+impl Client for RateLimitedClient {
+    async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+        self.acquire().await;
+        self.inner.call(request).await
+    }
+
+    async fn disconnect(&mut self) -> std::io::Result<()> {
+        self.inner.disconnect().await
+    }
+}
+
+/// Placeholder while the context of a client is moved, it is never called.
+#[derive(Debug)]
+struct NoClient;
+
+impl SlaveContext for NoClient {
+    fn set_slave(&mut self, _slave: tokio_modbus::Slave) {}
+}
+
+#[async_trait::async_trait]
+impl Client for NoClient {
+    async fn call(&mut self, _request: Request<'_>) -> tokio_modbus::Result<Response> {
+        Err(std::io::Error::from(std::io::ErrorKind::NotConnected).into())
+    }
+
+    async fn disconnect(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Holding registers 0x0000..0x0100 of a device, writes to `fail_write` fail with an exception.
+    /// While `disconnected` is set all requests fail with a transport error, disconnecting sets it.
+    /// While `disconnected` is set all requests fail with a transport error.
+    #[derive(Debug, Clone)]
+    struct MockClient {
+        registers: std::sync::Arc<std::sync::Mutex<Vec<u16>>>,
+        fail_write: Option<u16>,
+        read_write_multiple: bool,
+        disconnected: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl MockClient {
+        fn new() -> Self {
+            Self {
+                registers: std::sync::Arc::new(std::sync::Mutex::new(vec![219; 0x100])),
+                fail_write: None,
+                read_write_multiple: true,
+                disconnected: Default::default(),
+            }
+        }
+
+        fn client(&self) -> R4DCB08 {
+            R4DCB08::new(tokio_modbus::client::Context::from(
+                Box::new(self.clone()) as Box<dyn Client>
+            ))
+        }
+    }
+
+    impl SlaveContext for MockClient {
+        fn set_slave(&mut self, _slave: tokio_modbus::Slave) {}
+    }
+
+    #[async_trait::async_trait]
+    impl Client for MockClient {
+        async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+            if self.disconnected.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe).into());
+            }
+            let mut registers = self.registers.lock().unwrap();
+            match request {
+                Request::ReadHoldingRegisters(addr, quantity) => {
+                    Ok(Ok(Response::ReadHoldingRegisters(
+                        registers[addr as usize..(addr + quantity) as usize].to_vec(),
+                    )))
+                }
+                Request::WriteSingleRegister(addr, _) if self.fail_write == Some(addr) => {
+                    Ok(Err(ExceptionCode::ServerDeviceFailure))
+                }
+                Request::WriteSingleRegister(addr, value) => {
+                    registers[addr as usize] = value;
+                    Ok(Ok(Response::WriteSingleRegister(addr, value)))
+                }
+                Request::ReadWriteMultipleRegisters(addr, quantity, write_addr, words)
+                    if self.read_write_multiple =>
+                {
+                    registers[write_addr as usize..write_addr as usize + words.len()]
+                        .copy_from_slice(&words);
+                    Ok(Ok(Response::ReadWriteMultipleRegisters(
+                        registers[addr as usize..(addr + quantity) as usize].to_vec(),
+                    )))
+                }
+                _ => Ok(Err(ExceptionCode::IllegalFunction)),
+            }
+        }
+
+        async fn disconnect(&mut self) -> std::io::Result<()> {
+            self.disconnected
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn set_temperature_correction_all_atomic() {
+        block_on(async {
+            let mock = MockClient::new();
+            let mut client = mock.client();
+            client
+                .set_temperature_correction_all_atomic(&[1.0, -0.5])
+                .await
+                .unwrap();
+            assert_eq!(
+                client.read_temperature_correction().await.unwrap()[..3],
+                [1.0, -0.5, 21.9]
+            );
+
+            let mut mock = MockClient::new();
+            mock.fail_write = Some(proto::READ_TEMPERATURE_CORRECTION_REG_ADDR + 2);
+            let mut client = mock.client();
+            let Err(crate::tokio_error::Error::CorrectionWriteFailed { states, .. }) = client
+                .set_temperature_correction_all_atomic(&[1.0, 2.0, 3.0, 4.0])
+                .await
+            else {
+                panic!("CorrectionWriteFailed expected");
+            };
+            assert_eq!(
+                states,
+                [
+                    proto::CorrectionWriteState::RolledBack,
+                    proto::CorrectionWriteState::RolledBack,
+                    proto::CorrectionWriteState::Failed,
+                    proto::CorrectionWriteState::Unchanged,
+                ]
+            );
+            assert_eq!(
+                client.read_temperature_correction().await.unwrap(),
+                [21.9; 8]
+            );
+
+            assert!(matches!(
+                client
+                    .set_temperature_correction_all_atomic(&[0.0; 9])
+                    .await,
+                Err(crate::tokio_error::Error::R4DCB08Error(
+                    crate::Error::TooManyChannels(9)
+                ))
+            ));
+            assert!(matches!(
+                client
+                    .set_temperature_correction_all_atomic(&[0.0; 256])
+                    .await,
+                Err(crate::tokio_error::Error::R4DCB08Error(
+                    crate::Error::TooManyChannels(256)
+                ))
+            ));
+        });
+    }
+
+    #[test]
+    fn calibrate_channel_to() {
+        block_on(async {
+            let mock = MockClient::new();
+            let mut client = mock.client();
+            // the mock reads 21.9 °C with a correction of 21.9 °C
+            assert_eq!(client.calibrate_channel_to(1, 21.5).await.unwrap(), 21.5);
+            assert_eq!(client.read_temperature_correction().await.unwrap()[1], 21.5);
+            assert!(client.calibrate_channel_to(8, 21.5).await.is_err());
+        });
+    }
+
+    #[test]
+    fn read_temperatures_into() {
+        block_on(async {
+            let mut client = MockClient::new().client();
+            let mut raw = [0; proto::NUMBER_OF_CHANNELS as usize];
+            assert_eq!(
+                client.read_temperatures_into(&mut raw).await.unwrap(),
+                [21.9; 8]
+            );
+            assert_eq!(raw, [219; 8]);
+        });
+    }
+
+    #[test]
+    fn strict_correction_range() {
+        block_on(async {
+            let mut client = MockClient::new().client();
+            assert!(client.set_temperature_correction(0, 12.0).await.is_ok());
+            client.set_strict_correction_range(true);
+            assert!(matches!(
+                client.set_temperature_correction(0, 12.0).await,
+                Err(crate::tokio_error::Error::R4DCB08Error(
+                    crate::Error::CorrectionOutOfRange(_)
+                ))
+            ));
+            assert!(client.set_temperature_correction(0, -1.5).await.is_ok());
+        });
+    }
+
+    #[test]
+    fn write_corrections_and_read_temperatures() {
+        block_on(async {
+            for read_write_multiple in [true, false] {
+                let mock = MockClient {
+                    read_write_multiple,
+                    ..MockClient::new()
+                };
+                let mut client = mock.client();
+                assert_eq!(
+                    client
+                        .write_corrections_and_read_temperatures(&[1.0, -0.5])
+                        .await
+                        .unwrap(),
+                    [21.9; 8]
+                );
+                assert_eq!(
+                    client.read_temperature_correction().await.unwrap()[..3],
+                    [1.0, -0.5, 21.9]
+                );
+            }
+
+            // the single writes of the fallback are rolled back
+            let mut mock = MockClient {
+                read_write_multiple: false,
+                ..MockClient::new()
+            };
+            mock.fail_write = Some(proto::READ_TEMPERATURE_CORRECTION_REG_ADDR + 1);
+            let mut client = mock.client();
+            assert!(matches!(
+                client
+                    .write_corrections_and_read_temperatures(&[1.0, -0.5])
+                    .await,
+                Err(crate::tokio_error::Error::CorrectionWriteFailed { .. })
+            ));
+            assert_eq!(
+                client.read_temperature_correction().await.unwrap(),
+                [21.9; 8]
+            );
+        });
+    }
+
+    #[test]
+    fn apply_calibration_table() {
+        block_on(async {
+            let mut client = MockClient::new().client();
+            client
+                .apply_calibration_table(&[(21.5, 21.9), (20.0, 19.2)])
+                .await
+                .unwrap();
+            assert_eq!(
+                client.read_temperature_correction().await.unwrap()[..3],
+                [-0.4, 0.8, 21.9]
+            );
+
+            let mut client = MockClient::new().client();
+            assert!(matches!(
+                client
+                    .apply_calibration_table(&[(20.0, 20.0), (35.0, 20.0)])
+                    .await,
+                Err(crate::tokio_error::Error::R4DCB08Error(
+                    crate::Error::CorrectionOutOfRange(_)
+                ))
+            ));
+            assert_eq!(
+                client.read_temperature_correction().await.unwrap(),
+                [21.9; 8]
+            );
+        });
+    }
+
+    #[test]
+    fn read_baud_rate() {
+        block_on(async {
+            let mock = MockClient::new();
+            let mut client = mock.client();
+            mock.registers.lock().unwrap()[proto::READ_BAUD_RATE_REG_ADDR as usize] = 3;
+            assert_eq!(
+                client.read_baud_rate().await.unwrap(),
+                proto::BaudRate::B9600
+            );
+            mock.registers.lock().unwrap()[proto::READ_BAUD_RATE_REG_ADDR as usize] = 5;
+            assert!(matches!(
+                client.read_baud_rate().await,
+                Err(crate::tokio_error::Error::R4DCB08Error(
+                    crate::Error::InvalidBaudRate(5)
+                ))
+            ));
+        });
+    }
+
+    #[test]
+    fn is_address_in_use() {
+        block_on(async {
+            let mut client = MockClient::new().client();
+            client.set_slave_address(0x01);
+            let timeout = Duration::from_millis(100);
+            assert!(client.is_address_in_use(0x02, timeout).await.unwrap());
+            assert!(client.is_address_in_use(0x00, timeout).await.is_err());
+            assert_eq!(client.slave_address(), Some(0x01));
+
+            let mut client = MockClient::new().client();
+            assert_eq!(client.slave_address(), None);
+            assert!(matches!(
+                client.is_address_in_use(0x02, timeout).await,
+                Err(crate::tokio_error::Error::SlaveAddressUnknown)
+            ));
+        });
+    }
+
+    #[test]
+    fn rate_limit() {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap()
+            .block_on(async {
+                let mut client = MockClient::new().client().with_rate_limit(10.0).unwrap();
+                let start = tokio::time::Instant::now();
+                // the bucket holds 10 tokens, the 2 requests after the burst wait for a token each
+                for _ in 0..12 {
+                    client.read_temperature().await.unwrap();
+                }
+                assert_eq!(start.elapsed(), Duration::from_millis(200));
+            });
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                MockClient::new().client().with_rate_limit(rate),
+                Err(crate::tokio_error::Error::R4DCB08Error(
+                    crate::Error::InvalidRateLimit(_)
+                ))
+            ));
+        }
+    }
+
+    #[test]
+    fn close() {
+        block_on(async {
+            let mock = MockClient::new();
+            assert!(mock.client().close().await.is_ok());
+            assert!(mock.disconnected.load(std::sync::atomic::Ordering::SeqCst));
+        });
+    }
+
+    #[test]
+    fn polling() {
+        block_on(async {
+            let (sink, mut temperatures) = tokio::sync::mpsc::channel(1);
+            let handle = MockClient::new()
+                .client()
+                .start_polling(Duration::from_millis(1), sink);
+            assert!(handle.is_running());
+            for _ in 0..3 {
+                let rsp = temperatures.recv().await.unwrap().unwrap();
+                assert_eq!(rsp, vec![21.9; proto::NUMBER_OF_CHANNELS as usize]);
+            }
+            assert!(handle.stop().await.is_some());
+
+            // dropping the receiver ends the task
+            let (sink, temperatures) = tokio::sync::mpsc::channel(1);
+            drop(temperatures);
+            let handle = MockClient::new()
+                .client()
+                .start_polling(Duration::from_millis(1), sink);
+            assert!(handle.task.await.is_ok());
+        });
+    }
+
+    #[test]
+    fn polling_reconnecting() {
+        use std::sync::atomic::Ordering;
+
+        block_on(async {
+            let mock = MockClient::new();
+            let connects = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let connect = {
+                let mock = mock.clone();
+                let connects = connects.clone();
+                move || {
+                    let mock = mock.clone();
+                    let count = connects.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        // the first reconnect fails, the second one finds the device again
+                        if count == 1 {
+                            return Err(tokio_modbus::Error::Transport(
+                                std::io::ErrorKind::NotFound.into(),
+                            )
+                            .into());
+                        }
+                        mock.disconnected.store(false, Ordering::SeqCst);
+                        Ok(mock.client())
+                    }
+                }
+            };
+            let (sink, mut events) = tokio::sync::mpsc::channel(1);
+            let handle = start_polling_reconnecting(connect, Duration::from_millis(1), sink);
+            assert!(matches!(
+                events.recv().await.unwrap(),
+                StreamEvent::Reading(Ok(_))
+            ));
+
+            mock.disconnected.store(true, Ordering::SeqCst);
+            let mut received = Vec::new();
+            loop {
+                match events.recv().await.unwrap() {
+                    StreamEvent::Reading(Ok(_)) if received.is_empty() => continue,
+                    StreamEvent::Reading(rsp) => {
+                        assert!(rsp.is_ok());
+                        break;
+                    }
+                    StreamEvent::Reconnecting(_) => received.push("reconnecting"),
+                    StreamEvent::Reconnected => received.push("reconnected"),
+                }
+            }
+            assert_eq!(received, ["reconnecting", "reconnecting", "reconnected"]);
+            assert_eq!(connects.load(Ordering::SeqCst), 3);
+            assert!(handle.stop().await.unwrap().is_some());
+        });
+    }
 }
@@ -3,7 +3,157 @@ pub enum Error {
     #[error("R4DCB08 error: {0}")]
     R4DCB08Error(#[from] crate::Error),
     #[error("Modbus error: {0}")]
-    ModbusError(#[from] tokio_modbus::Error),
+    ModbusError(#[source] tokio_modbus::Error),
+    #[error("The device is no longer present, e.g. the USB adapter was unplugged: {0}")]
+    DeviceDisconnected(#[source] std::io::Error),
     #[error("Modbus exception: {0}")]
     ModbusException(#[from] tokio_modbus::ExceptionCode),
+    #[error("Cannot write the temperature corrections, states per channel {states:?}: {error}")]
+    CorrectionWriteFailed {
+        error: Box<Error>,
+        states: Vec<crate::protocol::CorrectionWriteState>,
+    },
+    #[error("The RS485 address of the client is unknown, set it with `set_slave_address` first")]
+    SlaveAddressUnknown,
+    #[error("All {} attempts failed, last error: {}", attempts.len(), attempts.last().map(ToString::to_string).unwrap_or_default())]
+    RetriesExhausted { attempts: Vec<Error> },
+    #[cfg(any(feature = "tokio-rtu", feature = "tokio-rtu-sync"))]
+    #[error("Serial port {0} not found, check the device path and that the adapter is plugged in")]
+    SerialPortNotFound(String),
+    #[cfg(any(feature = "tokio-rtu", feature = "tokio-rtu-sync"))]
+    #[error("Permission denied to open serial port {0}, add your user to the dialout group (e.g. `sudo usermod -aG dialout $USER`) and log in again")]
+    SerialPortPermissionDenied(String),
+    #[cfg(any(feature = "tokio-rtu", feature = "tokio-rtu-sync"))]
+    #[error("Cannot open serial port {0}: {1}")]
+    SerialPortOpen(String, #[source] std::io::Error),
+    #[cfg(feature = "tokio-rtu-sync")]
+    #[error(
+        "No device with address {address:#04x} responds on serial port {device} at any baud rate"
+    )]
+    BaudRateNotDetected { device: String, address: u8 },
+    #[cfg(feature = "tokio-tcp-sync")]
+    #[error("Cannot connect to {0} within {1:?}, check that the host is reachable")]
+    TcpConnectTimeout(std::net::SocketAddr, std::time::Duration),
+}
+
+impl From<tokio_modbus::Error> for Error {
+    fn from(error: tokio_modbus::Error) -> Self {
+        match error {
+            tokio_modbus::Error::Transport(error) if is_device_disconnected(&error) => {
+                Error::DeviceDisconnected(error)
+            }
+            error => Error::ModbusError(error),
+        }
+    }
+}
+
+impl Error {
+    /// Returns the error together with the likely causes and fixes, e.g. to paste into a support ticket.
+    pub fn diagnose(&self) -> String {
+        let hint = match self {
+            Error::ModbusError(tokio_modbus::Error::Transport(error))
+                if error.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                "The device did not respond in time. Check that the baud rate and the RS485 address match \
+                 the device, that A and B of the RS485 bus are not swapped and that the device is powered. \
+                 If several commands are sent in a row, increase the delay between them."
+            }
+            Error::ModbusError(tokio_modbus::Error::Transport(_)) => {
+                "The connection failed. Check the serial port or the network connection to the gateway."
+            }
+            Error::ModbusError(tokio_modbus::Error::Protocol(_)) => {
+                "The response does not match the request. Check that no other Modbus master uses the bus \
+                 and that a gateway does not swap the bytes of the registers, see `ByteOrder`."
+            }
+            Error::DeviceDisconnected(_) => {
+                "The serial adapter was removed, plug it in again and reconnect."
+            }
+            Error::ModbusException(
+                tokio_modbus::ExceptionCode::IllegalFunction
+                | tokio_modbus::ExceptionCode::IllegalDataAddress,
+            ) => {
+                "The device rejected the register. The firmware may use a different register layout, \
+                 see `RegisterLayout`, or the device is not a R4DCB08."
+            }
+            Error::ModbusException(_) => {
+                "The device rejected the request, check the value written and the firmware of the device."
+            }
+            Error::R4DCB08Error(
+                crate::Error::InvalidBaudRate(_)
+                | crate::Error::InvalidAddress(_)
+                | crate::Error::RegisterCountMismatch { .. },
+            ) => {
+                "The device answered with unexpected register values. A gateway may swap the bytes \
+                 of the registers, see `ByteOrder`, or the firmware uses a different register layout."
+            }
+            Error::R4DCB08Error(crate::Error::BroadcastAddressReported(_)) => {
+                "The device is misconfigured, reset it to the factory settings."
+            }
+            Error::R4DCB08Error(
+                crate::Error::CrcMismatch { .. }
+                | crate::Error::LrcMismatch { .. }
+                | crate::Error::InvalidFrame
+                | crate::Error::InvalidAsciiFrame,
+            ) => {
+                "A corrupted frame was received. Check the wiring and the termination of the RS485 bus \
+                 and that the baud rate matches the device."
+            }
+            Error::R4DCB08Error(_) => "The value passed is not supported by the device.",
+            Error::CorrectionWriteFailed { error, .. } => return error.diagnose(),
+            Error::RetriesExhausted { attempts } => match attempts.last() {
+                Some(error) => return error.diagnose(),
+                None => return self.to_string(),
+            },
+            // the message already describes the fix
+            Error::SlaveAddressUnknown => return self.to_string(),
+            #[cfg(any(feature = "tokio-rtu", feature = "tokio-rtu-sync"))]
+            Error::SerialPortNotFound(_)
+            | Error::SerialPortPermissionDenied(_)
+            | Error::SerialPortOpen(..) => return self.to_string(),
+            #[cfg(feature = "tokio-rtu-sync")]
+            Error::BaudRateNotDetected { .. } => {
+                "Check the wiring and that only one device is connected if the address is the broadcast address."
+            }
+            #[cfg(feature = "tokio-tcp-sync")]
+            Error::TcpConnectTimeout(..) => return self.to_string(),
+        };
+        format!("{self}\n{hint}")
+    }
+}
+
+/// Returns true for the I/O errors of a device which is no longer present, ENXIO (6) and ENODEV (19) on Unix.
+fn is_device_disconnected(error: &std::io::Error) -> bool {
+    cfg!(unix) && matches!(error.raw_os_error(), Some(6 | 19))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_disconnected() {
+        let error: Error =
+            tokio_modbus::Error::Transport(std::io::ErrorKind::TimedOut.into()).into();
+        assert!(matches!(error, Error::ModbusError(_)));
+        #[cfg(unix)]
+        for code in [6, 19] {
+            let error: Error =
+                tokio_modbus::Error::Transport(std::io::Error::from_raw_os_error(code)).into();
+            assert!(matches!(error, Error::DeviceDisconnected(_)));
+        }
+    }
+
+    #[test]
+    fn diagnose() {
+        let error: Error =
+            tokio_modbus::Error::Transport(std::io::ErrorKind::TimedOut.into()).into();
+        assert!(error.diagnose().contains("baud rate"));
+        let error = Error::RetriesExhausted {
+            attempts: vec![tokio_modbus::ExceptionCode::IllegalDataAddress.into()],
+        };
+        assert!(error
+            .diagnose()
+            .starts_with("Modbus exception: Illegal data address"));
+        assert!(error.diagnose().contains("RegisterLayout"));
+    }
 }
@@ -0,0 +1,491 @@
+use crate::{
+    protocol as proto,
+    tokio_async_client::R4DCB08,
+    tokio_pdu::{decode_response, encode_request, invalid_data},
+};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io,
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    time::Instant,
+};
+use tokio_modbus::{
+    prelude::{Client, Request, Response, SlaveContext},
+    Slave,
+};
+
+type Result<T> = std::result::Result<T, crate::tokio_error::Error>;
+
+const READ_WRITE_MULTIPLE_REGISTERS_FUNCTION_CODE: u8 = 0x17;
+
+/// The number of automatic reports kept by [`ReportQueue`], older reports are dropped.
+pub const REPORT_QUEUE_CAPACITY: usize = 64;
+
+/// An automatic report, the RS485 address of the device and the temperatures of all channels in °C,
+/// see [`proto::automatic_report_decode_frame`].
+pub type Report = (u8, Vec<f32>);
+
+/// Automatic reports received by a [`HybridClient`], shared with the client.
+#[derive(Debug, Clone, Default)]
+pub struct ReportQueue(Arc<Mutex<VecDeque<Report>>>);
+
+impl ReportQueue {
+    fn push(&self, report: Report) {
+        let mut reports = self.0.lock().expect("Report queue poisoned");
+        if reports.len() == REPORT_QUEUE_CAPACITY {
+            reports.pop_front();
+        }
+        reports.push_back(report);
+    }
+
+    /// Removes and returns the oldest report.
+    pub fn pop(&self) -> Option<Report> {
+        self.0.lock().expect("Report queue poisoned").pop_front()
+    }
+
+    /// Removes and returns all reports, oldest first.
+    pub fn drain(&self) -> Vec<Report> {
+        self.0
+            .lock()
+            .expect("Report queue poisoned")
+            .drain(..)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().expect("Report queue poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Modbus RTU client which sends requests while devices push automatic reports on the same bus.
+///
+/// tokio-modbus discards unexpected frames, so polling and automatic reporting cannot be mixed.
+/// This client owns the serial stream and serializes both: frames received before a request is sent,
+/// or received while waiting for a response which do not match the request, are decoded as automatic
+/// reports and buffered in the [`ReportQueue`]. Reports are therefore only picked up when a request is sent.
+///
+/// An automatic report has the same layout as the response to reading 8 registers, e.g. all temperatures.
+/// Such requests are widened by the adjacent register (the register before, or after for address 0),
+/// so the response is told apart by its length. The adjacent register must be readable,
+/// which holds for the register map of the R4DCB08.
+///
+/// After a corrupted or unknown frame, or if no response arrives within the timeout, the received data is
+/// discarded until the bus is silent, so the following frames are read from their start.
+#[derive(Debug)]
+pub struct HybridClient<T> {
+    stream: BufReader<T>,
+    slave: Slave,
+    reports: ReportQueue,
+    timeout: Duration,
+}
+
+/// The default time to wait for a response, see [`HybridClient::set_timeout`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The silence on the bus which ends the discarding of received data after an error,
+/// longer than the gap between the bytes of a frame at 1200 baud.
+const RESYNC_SILENCE: Duration = Duration::from_millis(50);
+
+impl<T> HybridClient<T> {
+    /// Constructs a new client for the device at `slave` waiting [`DEFAULT_TIMEOUT`] for responses.
+    pub fn new(stream: T, slave: Slave) -> Self
+    where
+        T: AsyncRead,
+    {
+        Self {
+            stream: BufReader::new(stream),
+            slave,
+            reports: ReportQueue::default(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets the time to wait for the response to a request.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns the queue of the automatic reports received by this client.
+    pub fn reports(&self) -> ReportQueue {
+        self.reports.clone()
+    }
+}
+
+/// Returns the request to send instead of `request` and the range of its response words to return,
+/// see [`HybridClient`].
+fn widen_request(request: &Request<'_>) -> Option<(Request<'static>, Range<usize>)> {
+    match request {
+        Request::ReadHoldingRegisters(address, quantity)
+            if *quantity == proto::NUMBER_OF_CHANNELS as u16 =>
+        {
+            let quantity = *quantity as usize;
+            Some(match address.checked_sub(1) {
+                Some(previous) => (
+                    Request::ReadHoldingRegisters(previous, quantity as u16 + 1),
+                    1..quantity + 1,
+                ),
+                None => (
+                    Request::ReadHoldingRegisters(*address, quantity as u16 + 1),
+                    0..quantity,
+                ),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "No response from the device")
+}
+
+impl<T> HybridClient<T>
+where
+    T: AsyncRead + Unpin,
+{
+    /// Reads the frames already received without waiting for more data.
+    async fn receive_pending(&mut self) -> io::Result<()> {
+        // the timeout polls the buffer once, filling the buffer is cancel safe
+        while let Ok(buffer) = tokio::time::timeout(Duration::ZERO, self.stream.fill_buf()).await {
+            if buffer?.is_empty() {
+                break;
+            }
+            match self.read_frame(Instant::now() + self.timeout).await {
+                Ok(frame) => {
+                    if let Ok(report) = proto::automatic_report_decode_frame(&frame) {
+                        self.reports.push(report);
+                    }
+                }
+                // the received data was discarded, the request can be sent
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::InvalidData | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a RTU frame until `deadline`, on an error the received data is discarded until the bus is silent.
+    async fn read_frame(&mut self, deadline: Instant) -> io::Result<Vec<u8>> {
+        let frame = match tokio::time::timeout_at(deadline, self.read_frame_unsynchronized()).await
+        {
+            Ok(Ok(frame)) => return Ok(frame),
+            Ok(Err(error)) if error.kind() == io::ErrorKind::UnexpectedEof => return Err(error),
+            Ok(Err(error)) => Err(error),
+            Err(_) => Err(timed_out()),
+        };
+        self.resynchronize().await?;
+        frame
+    }
+
+    /// Discards the received data until the bus is silent for [`RESYNC_SILENCE`].
+    async fn resynchronize(&mut self) -> io::Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let buffered = self.stream.buffer().len();
+            self.stream.consume(buffered);
+            match tokio::time::timeout(RESYNC_SILENCE, self.stream.fill_buf()).await {
+                Ok(Ok(buffer)) if !buffer.is_empty() && Instant::now() < deadline => {}
+                Ok(Err(error)) => return Err(error),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Reads a RTU frame, the length is derived from the function code.
+    async fn read_frame_unsynchronized(&mut self) -> io::Result<Vec<u8>> {
+        let mut frame = vec![0; 2];
+        self.stream.read_exact(&mut frame).await?;
+        let remaining = match frame[1] {
+            code if code & 0x80 != 0 => 3,
+            proto::AUTOMATIC_REPORT_FUNCTION_CODE
+            | proto::REPORT_SLAVE_ID_FUNCTION_CODE
+            | READ_WRITE_MULTIPLE_REGISTERS_FUNCTION_CODE => {
+                let count = self.stream.read_u8().await?;
+                frame.push(count);
+                count as usize + 2
+            }
+            proto::DIAGNOSTICS_FUNCTION_CODE | 0x06 => 6,
+            code => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    crate::Error::UnexpectedResponse(code),
+                ))
+            }
+        };
+        let start = frame.len();
+        frame.resize(start + remaining, 0);
+        self.stream.read_exact(&mut frame[start..]).await?;
+        let (data, crc) = frame.split_at(frame.len() - 2);
+        let expected = proto::modbus_crc16(data);
+        let actual = u16::from_le_bytes([crc[0], crc[1]]);
+        if expected != actual {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                crate::Error::CrcMismatch { expected, actual },
+            ));
+        }
+        Ok(frame)
+    }
+}
+
+impl<T> SlaveContext for HybridClient<T> {
+    fn set_slave(&mut self, slave: Slave) {
+        self.slave = slave;
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> Client for HybridClient<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + Debug,
+{
+    async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+        self.receive_pending().await?;
+        let widened = widen_request(&request);
+        let sent = match &widened {
+            Some((widened, _)) => widened,
+            None => &request,
+        };
+        let frame = proto::rtu_encode_frame(self.slave.into(), &encode_request(sent)?);
+        self.stream.get_mut().write_all(&frame).await?;
+        self.stream.get_mut().flush().await?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let frame = self.read_frame(deadline).await?;
+            let pdu = &frame[1..frame.len() - 2];
+            if frame[0] == u8::from(self.slave) {
+                if let Ok(response) = decode_response(sent, pdu) {
+                    return Ok(match (response, &widened) {
+                        (Ok(Response::ReadHoldingRegisters(words)), Some((_, range))) => Ok(
+                            Response::ReadHoldingRegisters(words[range.clone()].to_vec()),
+                        ),
+                        (response, _) => response,
+                    });
+                }
+            }
+            match proto::automatic_report_decode_frame(&frame) {
+                Ok(report) => self.reports.push(report),
+                Err(_) => {
+                    return Err(invalid_data(crate::Error::UnexpectedResponse(
+                        request.function_code().value(),
+                    )))
+                }
+            }
+        }
+    }
+
+    async fn disconnect(&mut self) -> io::Result<()> {
+        self.stream.get_mut().shutdown().await
+    }
+}
+
+/// Opens the serial port `device` and connects to the device at `slave`, the automatic reports are returned in the queue.
+///
+/// * 'builder' - The settings of the serial port, its path is replaced by `device`,
+///   which is named in the errors of opening the port, see [`crate::tokio_serial::open_error`].
+/// * 'timeout' - The time to wait for the response to a request, see [`HybridClient::set_timeout`].
+///
+/// Must be called within a tokio runtime.
+pub fn connect_slave(
+    device: &str,
+    builder: &tokio_serial::SerialPortBuilder,
+    slave: Slave,
+    timeout: Duration,
+) -> Result<(R4DCB08, ReportQueue)> {
+    let port = tokio_serial::SerialStream::open(&builder.clone().path(device))
+        .map_err(|error| crate::tokio_serial::open_error(device, error.into()))?;
+    let mut client = HybridClient::new(port, slave);
+    client.set_timeout(timeout);
+    let reports = client.reports();
+    let mut d = R4DCB08::new(tokio_modbus::client::Context::from(
+        Box::new(client) as Box<dyn Client>
+    ));
+    d.set_transport(proto::Framing::Rtu);
+    d.set_slave_address(slave.into());
+    Ok((d, reports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Automatic report of the device at 0x01 with 21.9 °C in all channels.
+    fn report_frame() -> Vec<u8> {
+        let mut pdu = vec![proto::AUTOMATIC_REPORT_FUNCTION_CODE, 0x10];
+        for _ in 0..proto::NUMBER_OF_CHANNELS {
+            pdu.extend_from_slice(&[0x00, 0xDB]);
+        }
+        proto::rtu_encode_frame(0x01, &pdu)
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// Reads the request and checks it against `request`.
+    async fn expect_request(device_stream: &mut tokio::io::DuplexStream, request: &[u8]) {
+        let request = proto::rtu_encode_frame(0x01, request);
+        let mut received = vec![0; request.len()];
+        device_stream.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, request);
+    }
+
+    #[test]
+    fn hybrid_client() {
+        block_on(async {
+            let (stream, mut device_stream) = tokio::io::duplex(256);
+            let client = HybridClient::new(stream, Slave(0x01));
+            let reports = client.reports();
+            let mut d = R4DCB08::new(tokio_modbus::client::Context::from(
+                Box::new(client) as Box<dyn Client>
+            ));
+            // a report pushed before the request is sent
+            device_stream.write_all(&report_frame()).await.unwrap();
+            let task = tokio::spawn(async move {
+                expect_request(&mut device_stream, &[0x03, 0x00, 0xFE, 0x00, 0x01]).await;
+                // a report pushed before the response
+                device_stream.write_all(&report_frame()).await.unwrap();
+                device_stream
+                    .write_all(&proto::rtu_encode_frame(0x01, &[0x03, 0x02, 0x00, 0x05]))
+                    .await
+                    .unwrap();
+            });
+            let rsp = d.read_address().await;
+            task.await.unwrap();
+            assert_eq!(rsp.unwrap(), 0x05);
+            assert_eq!(
+                reports.drain(),
+                [(0x01, vec![21.9; 8]), (0x01, vec![21.9; 8])]
+            );
+            assert!(reports.is_empty());
+        });
+    }
+
+    #[test]
+    fn read_all_temperatures() {
+        block_on(async {
+            let (stream, mut device_stream) = tokio::io::duplex(256);
+            let client = HybridClient::new(stream, Slave(0x01));
+            let reports = client.reports();
+            let mut d = R4DCB08::new(tokio_modbus::client::Context::from(
+                Box::new(client) as Box<dyn Client>
+            ));
+            let task = tokio::spawn(async move {
+                // the request is widened by the first correction register
+                expect_request(&mut device_stream, &[0x03, 0x00, 0x00, 0x00, 0x09]).await;
+                // a report with the layout of the response to the original request
+                device_stream.write_all(&report_frame()).await.unwrap();
+                let mut pdu = vec![0x03, 0x12];
+                for _ in 0..proto::NUMBER_OF_CHANNELS {
+                    pdu.extend_from_slice(&[0x00, 0xD2]);
+                }
+                pdu.extend_from_slice(&[0x00, 0x05]);
+                device_stream
+                    .write_all(&proto::rtu_encode_frame(0x01, &pdu))
+                    .await
+                    .unwrap();
+            });
+            let rsp = d.read_temperature().await;
+            task.await.unwrap();
+            assert_eq!(rsp.unwrap(), [21.0; 8]);
+            assert_eq!(reports.drain(), [(0x01, vec![21.9; 8])]);
+        });
+        assert_eq!(
+            widen_request(&Request::ReadHoldingRegisters(0x08, 8)),
+            Some((Request::ReadHoldingRegisters(0x07, 9), 1..9))
+        );
+        assert_eq!(widen_request(&Request::ReadHoldingRegisters(0x08, 1)), None);
+    }
+
+    #[test]
+    fn resynchronize() {
+        block_on(async {
+            let (stream, mut device_stream) = tokio::io::duplex(256);
+            let mut client = HybridClient::new(stream, Slave(0x01));
+            client.set_timeout(Duration::from_millis(500));
+            let mut d = R4DCB08::new(tokio_modbus::client::Context::from(
+                Box::new(client) as Box<dyn Client>
+            ));
+            let read_address = [0x03, 0x00, 0xFE, 0x00, 0x01];
+            let response = proto::rtu_encode_frame(0x01, &[0x03, 0x02, 0x00, 0x05]);
+            let task = tokio::spawn(async move {
+                // a corrupted response followed by the bytes of another frame
+                expect_request(&mut device_stream, &read_address).await;
+                let mut corrupted = response.clone();
+                corrupted[4] ^= 0xFF;
+                corrupted.extend_from_slice(&report_frame()[..7]);
+                device_stream.write_all(&corrupted).await.unwrap();
+
+                // an unknown function code followed by the rest of its frame
+                expect_request(&mut device_stream, &read_address).await;
+                device_stream
+                    .write_all(&[0x01, 0x2B, 0x0E, 0x01, 0x00, 0x12, 0x34])
+                    .await
+                    .unwrap();
+
+                // no response
+                expect_request(&mut device_stream, &read_address).await;
+
+                expect_request(&mut device_stream, &read_address).await;
+                device_stream.write_all(&response).await.unwrap();
+                device_stream
+            });
+            let error = d.read_address().await.unwrap_err();
+            assert!(matches!(
+                error,
+                crate::tokio_error::Error::ModbusError(tokio_modbus::Error::Transport(error))
+                    if error.kind() == io::ErrorKind::InvalidData
+            ));
+            assert!(d.read_address().await.is_err());
+            let error = d.read_address().await.unwrap_err();
+            assert!(matches!(
+                error,
+                crate::tokio_error::Error::ModbusError(tokio_modbus::Error::Transport(error))
+                    if error.kind() == io::ErrorKind::TimedOut
+            ));
+            // the leftovers of the failed responses are discarded
+            assert_eq!(d.read_address().await.unwrap(), 0x05);
+            task.await.unwrap();
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn connect_slave_not_found() {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let builder = tokio_serial::new("", 9600);
+                assert!(matches!(
+                    super::connect_slave(
+                        "/dev/tempcol-missing",
+                        &builder,
+                        Slave(0x01),
+                        DEFAULT_TIMEOUT
+                    ),
+                    Err(crate::tokio_error::Error::SerialPortNotFound(device))
+                        if device == "/dev/tempcol-missing"
+                ));
+            });
+    }
+}
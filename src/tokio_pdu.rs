@@ -0,0 +1,95 @@
+use std::io;
+use tokio_modbus::{
+    bytes::Bytes,
+    prelude::{ExceptionCode, Request, Response},
+};
+
+pub(crate) fn invalid_data(error: crate::Error) -> tokio_modbus::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error).into()
+}
+
+/// Encodes the PDU of the requests used by [`crate::tokio_async_client::R4DCB08`].
+pub(crate) fn encode_request(request: &Request<'_>) -> io::Result<Vec<u8>> {
+    let function_code = request.function_code().value();
+    let mut pdu = vec![function_code];
+    match request {
+        Request::ReadHoldingRegisters(address, quantity) => {
+            pdu.extend_from_slice(&address.to_be_bytes());
+            pdu.extend_from_slice(&quantity.to_be_bytes());
+        }
+        Request::WriteSingleRegister(address, value) => {
+            pdu.extend_from_slice(&address.to_be_bytes());
+            pdu.extend_from_slice(&value.to_be_bytes());
+        }
+        Request::ReadWriteMultipleRegisters(address, quantity, write_address, words) => {
+            pdu.extend_from_slice(&address.to_be_bytes());
+            pdu.extend_from_slice(&quantity.to_be_bytes());
+            pdu.extend_from_slice(&write_address.to_be_bytes());
+            pdu.extend_from_slice(&(words.len() as u16).to_be_bytes());
+            pdu.push(2 * words.len() as u8);
+            for word in words.iter() {
+                pdu.extend_from_slice(&word.to_be_bytes());
+            }
+        }
+        Request::Custom(_, data) => pdu.extend_from_slice(data),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Function code {function_code:#04x} is not supported by this client"),
+            ))
+        }
+    }
+    Ok(pdu)
+}
+
+/// Decodes the response PDU to the request.
+pub(crate) fn decode_response(request: &Request<'_>, pdu: &[u8]) -> tokio_modbus::Result<Response> {
+    let function_code = request.function_code().value();
+    let unexpected = || invalid_data(crate::Error::UnexpectedResponse(function_code));
+    match pdu {
+        [code, exception] if *code == function_code | 0x80 => {
+            return Ok(Err(ExceptionCode::new(*exception)));
+        }
+        [code, ..] if *code == function_code => {}
+        _ => return Err(unexpected()),
+    }
+    let data = &pdu[1..];
+    let response = match request {
+        Request::ReadHoldingRegisters(_, quantity) => {
+            Response::ReadHoldingRegisters(decode_words(data, *quantity).ok_or_else(unexpected)?)
+        }
+        Request::ReadWriteMultipleRegisters(_, quantity, _, _) => {
+            Response::ReadWriteMultipleRegisters(
+                decode_words(data, *quantity).ok_or_else(unexpected)?,
+            )
+        }
+        Request::WriteSingleRegister(address, value) => {
+            let mut echo = address.to_be_bytes().to_vec();
+            echo.extend_from_slice(&value.to_be_bytes());
+            if data != echo {
+                return Err(unexpected());
+            }
+            Response::WriteSingleRegister(*address, *value)
+        }
+        Request::Custom(code, _) => Response::Custom(*code, Bytes::copy_from_slice(data)),
+        _ => return Err(unexpected()),
+    };
+    Ok(Ok(response))
+}
+
+/// Decodes the byte count followed by `quantity` register values.
+fn decode_words(data: &[u8], quantity: u16) -> Option<Vec<u16>> {
+    match data.split_first() {
+        Some((count, words))
+            if *count as usize == words.len() && words.len() == 2 * quantity as usize =>
+        {
+            Some(
+                words
+                    .chunks_exact(2)
+                    .map(|word| u16::from_be_bytes([word[0], word[1]]))
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
@@ -1,11 +1,181 @@
+use crate::protocol as proto;
+use std::{
+    io::Read,
+    time::{Duration, Instant},
+};
+
 pub const PARITY: &tokio_serial::Parity = &tokio_serial::Parity::None;
 pub const STOP_BITS: &tokio_serial::StopBits = &tokio_serial::StopBits::One;
 pub const DATA_BITS: &tokio_serial::DataBits = &tokio_serial::DataBits::Eight;
 
-pub fn serial_port_builder(device: &String, baud_rate: u32) -> tokio_serial::SerialPortBuilder {
-    tokio_serial::new(device, baud_rate)
+/// Creates the serial port builder for the R4DCB08.
+///
+/// * 'exclusive' - Open the port with exclusive access, so no other process can open it concurrently.
+///   This is only supported on Unix, on other platforms serial ports are always opened exclusively.
+pub fn serial_port_builder(
+    device: &String,
+    baud_rate: u32,
+    exclusive: bool,
+) -> tokio_serial::SerialPortBuilder {
+    let builder = tokio_serial::new(device, baud_rate)
         .parity(*PARITY)
         .stop_bits(*STOP_BITS)
         .data_bits(*DATA_BITS)
-        .flow_control(tokio_serial::FlowControl::None)
+        .flow_control(tokio_serial::FlowControl::None);
+    #[cfg(unix)]
+    let builder = builder.exclusive(exclusive);
+    #[cfg(not(unix))]
+    let _ = exclusive;
+    builder
+}
+
+/// Maps an I/O error of opening the serial port `device` to a typed error.
+///
+/// Missing devices and missing permissions are reported with an actionable message.
+pub fn open_error(device: &str, error: std::io::Error) -> crate::tokio_error::Error {
+    use crate::tokio_error::Error;
+    match error.kind() {
+        std::io::ErrorKind::NotFound => Error::SerialPortNotFound(device.to_string()),
+        std::io::ErrorKind::PermissionDenied => {
+            Error::SerialPortPermissionDenied(device.to_string())
+        }
+        _ => Error::SerialPortOpen(device.to_string(), error),
+    }
+}
+
+/// Detects the baud rate of the device with the RS485 `address` connected to the serial port `device`.
+///
+/// Each supported baud rate is tried in turn by reading the temperatures, waiting `delay` between the attempts.
+/// Unlike scanning with the broadcast address, other devices may be connected to the bus.
+/// The serial port is opened with exclusive access.
+#[cfg(feature = "tokio-rtu-sync")]
+pub fn detect_baud_rate(
+    device: &str,
+    address: u8,
+    timeout: Duration,
+    delay: Duration,
+) -> Result<proto::BaudRate, crate::tokio_error::Error> {
+    for (index, baud_rate) in proto::BaudRate::ALL.into_iter().enumerate() {
+        if index > 0 {
+            std::thread::sleep(delay);
+        }
+        match probe_baud_rate(device, address, baud_rate, timeout) {
+            Ok(()) => return Ok(baud_rate),
+            // Errors opening the serial port are independent of the baud rate
+            Err(
+                error @ (crate::tokio_error::Error::SerialPortNotFound(_)
+                | crate::tokio_error::Error::SerialPortPermissionDenied(_)
+                | crate::tokio_error::Error::SerialPortOpen(..)),
+            ) => return Err(error),
+            Err(_) => {}
+        }
+    }
+    Err(crate::tokio_error::Error::BaudRateNotDetected {
+        device: device.to_string(),
+        address,
+    })
+}
+
+/// Tries every supported baud rate and returns the outcome of each, unlike [`detect_baud_rate`]
+/// it does not stop at the first baud rate the device responds to.
+///
+/// A device responding at some baud rates only can reveal signal integrity issues of the bus.
+/// The serial port is opened with exclusive access.
+#[cfg(feature = "tokio-rtu-sync")]
+pub fn probe_all_baud_rates(
+    device: &str,
+    address: u8,
+    timeout: Duration,
+) -> Vec<(proto::BaudRate, Result<(), crate::tokio_error::Error>)> {
+    proto::BaudRate::ALL
+        .into_iter()
+        .map(|baud_rate| {
+            (
+                baud_rate,
+                probe_baud_rate(device, address, baud_rate, timeout),
+            )
+        })
+        .collect()
+}
+
+/// Reads the temperatures of the device with the RS485 `address` at `baud_rate`.
+#[cfg(feature = "tokio-rtu-sync")]
+fn probe_baud_rate(
+    device: &str,
+    address: u8,
+    baud_rate: proto::BaudRate,
+    timeout: Duration,
+) -> Result<(), crate::tokio_error::Error> {
+    let spec = crate::connection::ConnectionSpec::Rtu {
+        device: device.to_string(),
+        baud_rate: baud_rate.bits_per_second(),
+        address,
+        exclusive: true,
+    };
+    spec.connect(timeout)?.read_temperature()?;
+    Ok(())
+}
+
+/// Receives the temperature frames the device pushes when automatic reporting is enabled.
+///
+/// Note: The serial port must not be used by a Modbus client at the same time.
+pub struct AutoReportListener {
+    port: Box<dyn tokio_serial::SerialPort>,
+    buffer: Vec<u8>,
+}
+
+impl AutoReportListener {
+    /// Opens the serial port to listen for automatic reports.
+    pub fn open(
+        device: &String,
+        baud_rate: u32,
+        exclusive: bool,
+    ) -> Result<Self, crate::tokio_error::Error> {
+        let port = serial_port_builder(device, baud_rate, exclusive)
+            .open()
+            .map_err(|error| open_error(device, error.into()))?;
+        Ok(Self {
+            port,
+            buffer: Vec::with_capacity(2 * proto::AUTOMATIC_REPORT_FRAME_LEN),
+        })
+    }
+
+    /// Waits for the next frame and returns the RS485 address of the device and the temperatures in °C.
+    ///
+    /// Bytes which do not belong to a valid frame are skipped.
+    /// If no frame is received within `timeout` a `TimedOut` transport error is returned.
+    pub fn next_frame(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<(u8, Vec<f32>), crate::tokio_error::Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            while self.buffer.len() >= proto::AUTOMATIC_REPORT_FRAME_LEN {
+                let frame = &self.buffer[..proto::AUTOMATIC_REPORT_FRAME_LEN];
+                if let Ok(frame) = proto::automatic_report_decode_frame(frame) {
+                    self.buffer.drain(..proto::AUTOMATIC_REPORT_FRAME_LEN);
+                    return Ok(frame);
+                }
+                // Resynchronize on the next byte
+                self.buffer.remove(0);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(transport_error(std::io::ErrorKind::TimedOut.into()));
+            }
+            self.port
+                .set_timeout(remaining)
+                .map_err(|error| transport_error(error.into()))?;
+            let mut chunk = [0u8; 64];
+            match self.port.read(&mut chunk) {
+                Ok(len) => self.buffer.extend_from_slice(&chunk[..len]),
+                Err(error) if error.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(error) => return Err(transport_error(error)),
+            }
+        }
+    }
+}
+
+fn transport_error(error: std::io::Error) -> crate::tokio_error::Error {
+    tokio_modbus::Error::Transport(error).into()
 }
@@ -1,17 +1,121 @@
 use crate::protocol as proto;
-use std::time::Duration;
-use tokio_modbus::prelude::{SyncReader, SyncWriter};
+use std::{borrow::Cow, collections::BTreeMap, fmt, time::Duration};
+use tokio_modbus::prelude::{
+    ExceptionCode, Request, Response, SlaveContext, SyncClient, SyncReader, SyncWriter,
+};
 
 type Result<T> = std::result::Result<T, crate::tokio_error::Error>;
 
 pub struct R4DCB08 {
     ctx: tokio_modbus::client::sync::Context,
+    layout: proto::RegisterLayout,
+    correction_sentinel: proto::SentinelDecoding,
+    byte_order: proto::ByteOrder,
+    transport: Option<proto::Framing>,
+    ignore_automatic_report_timeout: bool,
+    strict_correction_range: bool,
+    slave_address: Option<u8>,
+    close_guard: crate::close_guard::CloseGuard,
 }
 
 impl R4DCB08 {
     /// Constructs a new R4DCB08 client
     pub fn new(ctx: tokio_modbus::client::sync::Context) -> Self {
-        Self { ctx }
+        Self {
+            ctx,
+            layout: proto::RegisterLayout::default(),
+            correction_sentinel: proto::SentinelDecoding::default(),
+            byte_order: proto::ByteOrder::default(),
+            transport: None,
+            ignore_automatic_report_timeout: false,
+            strict_correction_range: false,
+            slave_address: None,
+            close_guard: Default::default(),
+        }
+    }
+
+    /// Constructs a new R4DCB08 client using a custom register layout
+    ///
+    /// Fails if the layout is invalid, see [`proto::RegisterLayout::validate`].
+    pub fn with_layout(
+        ctx: tokio_modbus::client::sync::Context,
+        layout: proto::RegisterLayout,
+    ) -> Result<Self> {
+        layout.validate()?;
+        let mut d = Self::new(ctx);
+        d.layout = layout;
+        Ok(d)
+    }
+
+    /// Returns the register layout used by this client.
+    pub fn layout(&self) -> &proto::RegisterLayout {
+        &self.layout
+    }
+
+    /// Sets how the register value 0x8000 of a temperature correction is decoded.
+    ///
+    /// The default decodes 0x8000 as NaN, see [`proto::SentinelDecoding`].
+    pub fn set_correction_sentinel(&mut self, sentinel: proto::SentinelDecoding) {
+        self.correction_sentinel = sentinel;
+    }
+
+    /// Returns the transport of the connection, `None` if it is unknown.
+    ///
+    /// E.g. to keep the inter-frame delay required by Modbus/RTU only for RTU connections.
+    pub fn transport(&self) -> Option<proto::Framing> {
+        self.transport
+    }
+
+    /// Sets the transport of the connection, clients created by the connect functions of this crate set it.
+    pub fn set_transport(&mut self, transport: proto::Framing) {
+        self.transport = Some(transport);
+    }
+
+    /// Treats a timeout of [`Self::set_automatic_report`] as success, disabled by default.
+    ///
+    /// Some firmware starts pushing reports immediately and does not respond to the write enabling them,
+    /// so the write looks failed although it worked.
+    pub fn set_ignore_automatic_report_timeout(&mut self, ignore: bool) {
+        self.ignore_automatic_report_timeout = ignore;
+    }
+
+    /// Rejects temperature corrections outside of [`proto::CORRECTION_MIN`] to [`proto::CORRECTION_MAX`], disabled by default.
+    ///
+    /// See [`proto::correction_in_range`], the caller should warn about such corrections when disabled.
+    pub fn set_strict_correction_range(&mut self, strict: bool) {
+        self.strict_correction_range = strict;
+    }
+
+    /// Encodes a temperature correction, checking the range if [`Self::set_strict_correction_range`] is enabled.
+    fn encode_correction(&self, correction: f32) -> std::result::Result<u16, crate::Error> {
+        if self.strict_correction_range {
+            proto::write_temperature_correction_check_range(correction)?;
+        }
+        proto::degree_celsius_encode(correction)
+    }
+
+    /// Changes the RS485 address (Modbus slave) of the device the client talks to.
+    ///
+    /// Unlike [`Self::set_address`] nothing is written to the device, e.g. to configure several devices on one bus.
+    pub fn set_slave_address(&mut self, address: u8) {
+        self.ctx.set_slave(tokio_modbus::Slave(address));
+        self.slave_address = Some(address);
+    }
+
+    /// Returns the RS485 address (Modbus slave) of the device the client talks to, e.g. for log messages.
+    ///
+    /// `None` if the address is unknown, because the client was constructed from a context
+    /// which cannot report its address.
+    pub fn slave_address(&self) -> Option<u8> {
+        self.slave_address
+    }
+
+    /// Sets the byte order of the register values, see [`proto::ByteOrder`].
+    ///
+    /// Use [`proto::ByteOrder::LittleEndian`] for gateways swapping the bytes of each register.
+    /// The byte order is applied to all read and written register values.
+    pub fn set_byte_order(&mut self, byte_order: proto::ByteOrder) {
+        self.byte_order = byte_order;
     }
 
     /// Sets the modbus context timeout.
@@ -23,30 +127,168 @@ impl R4DCB08 {
         self.ctx.timeout()
     }
 
+    /// Closes the connection, e.g. to release a serial port opened with exclusive access.
+    ///
+    /// The serial port or TCP connection is released when this returns, so another client
+    /// or process can open it immediately. This is the same as dropping the client but explicit,
+    /// with the `log` feature dropping a client which was not closed is logged.
+    pub fn close(mut self) {
+        self.close_guard.close();
+    }
+
     /// Read the current temperature from all channels in °C.
     /// If a channel is not connected or an error is occurred, NaN is returned.
     ///
     /// The returned temperature is corrected by the temperature correction
     pub fn read_temperature(&mut self) -> Result<Vec<f32>> {
+        Ok(self.read_temperatures_with_raw()?.0)
+    }
+
+    /// Read the current temperature from all channels in signed tenths of a degree Celsius.
+    ///
+    /// No float conversion is involved, e.g. for integer-only pipelines. `None` indicates no sensor or an error.
+    pub fn read_temperatures_tenths(&mut self) -> Result<Vec<Option<i16>>> {
         let rsp = self.ctx.read_holding_registers(
-            proto::READ_TEMPERATURE_REG_ADDR,
+            self.layout.temperature_reg_addr,
             proto::READ_TEMPERATURE_REG_QUAN,
         )??;
         Ok(rsp
             .iter()
-            .map(|value| proto::degree_celsius_decode(*value))
+            .map(|value| proto::degree_celsius_decode_tenths(self.byte_order.apply(*value)))
+            .collect())
+    }
+
+    /// Read the current temperature from all channels in °C like [`Self::read_temperature`]
+    /// together with the register values as received, e.g. to debug suspicious temperatures.
+    pub fn read_temperatures_with_raw(&mut self) -> Result<(Vec<f32>, Vec<u16>)> {
+        let rsp = self.ctx.read_holding_registers(
+            self.layout.temperature_reg_addr,
+            proto::READ_TEMPERATURE_REG_QUAN,
+        )??;
+        let temperatures = rsp
+            .iter()
+            .map(|value| proto::degree_celsius_decode(self.byte_order.apply(*value)))
+            .collect::<Vec<_>>();
+        Ok((temperatures, rsp))
+    }
+
+    /// Read the current temperature from all channels in °C like [`Self::read_temperature`] without allocating the result.
+    ///
+    /// The register values are copied into `raw`, which can be reused between reads, and the temperatures
+    /// are returned in a fixed size array. Note that tokio-modbus still allocates the response internally.
+    /// Fails with [`crate::Error::RegisterCountMismatch`] if the device does not respond with all channels.
+    pub fn read_temperatures_into(
+        &mut self,
+        raw: &mut [u16; proto::NUMBER_OF_CHANNELS as usize],
+    ) -> Result<[f32; proto::NUMBER_OF_CHANNELS as usize]> {
+        let rsp = self.ctx.read_holding_registers(
+            self.layout.temperature_reg_addr,
+            proto::READ_TEMPERATURE_REG_QUAN,
+        )??;
+        if rsp.len() != raw.len() {
+            return Err(crate::Error::RegisterCountMismatch {
+                expected: proto::READ_TEMPERATURE_REG_QUAN,
+                actual: rsp.len(),
+            }
+            .into());
+        }
+        raw.copy_from_slice(&rsp);
+        Ok(raw.map(|value| proto::degree_celsius_decode(self.byte_order.apply(value))))
+    }
+
+    /// Read the current temperature from all channels with the status of each channel.
+    ///
+    /// Unlike [`Self::read_temperature`] a missing sensor is reported explicitly instead of NaN,
+    /// see [`proto::ChannelStatus`].
+    pub fn read_temperatures_detailed(&mut self) -> Result<Vec<proto::ChannelStatus>> {
+        let rsp = self.ctx.read_holding_registers(
+            self.layout.temperature_reg_addr,
+            proto::READ_TEMPERATURE_REG_QUAN,
+        )??;
+        Ok(rsp
+            .iter()
+            .map(|value| proto::channel_status_decode(self.byte_order.apply(*value)))
             .collect::<Vec<_>>())
     }
 
+    /// Read the temperature from all channels `samples` times, `spacing` apart, and return the average per channel in °C.
+    ///
+    /// This reduces the jitter of the sensors. The `nan_policy` defines whether a NaN sample of a channel
+    /// makes its average NaN or is skipped, see [`crate::analysis::NanPolicy`]. At least one sample is read.
+    pub fn read_temperatures_averaged(
+        &mut self,
+        samples: u8,
+        spacing: Duration,
+        nan_policy: crate::analysis::NanPolicy,
+    ) -> Result<Vec<f32>> {
+        let mut readings = Vec::with_capacity(samples as usize);
+        for sample in 0..samples.max(1) {
+            if sample > 0 {
+                std::thread::sleep(spacing);
+            }
+            readings.push(self.read_temperature()?);
+        }
+        Ok(crate::analysis::average_samples(&readings, nan_policy))
+    }
+
+    /// Read the current temperature of `count` contiguous channels beginning at channel `start` in °C.
+    /// If a channel is not connected or an error is occurred, NaN is returned.
+    ///
+    /// * 'start' - First temperature sensor channel 0 to 7.
+    /// * 'count' - Number of channels to read, `start + count` must not exceed 8.
+    pub fn read_temperature_range(&mut self, start: u8, count: u8) -> Result<Vec<f32>> {
+        proto::read_temperature_range_check(start, count)?;
+        let rsp = self.ctx.read_holding_registers(
+            self.layout.temperature_reg_addr + start as u16,
+            count as u16,
+        )??;
+        Ok(rsp
+            .iter()
+            .map(|value| proto::degree_celsius_decode(self.byte_order.apply(*value)))
+            .collect::<Vec<_>>())
+    }
+
+    /// Read the current temperature of all connected channels in °C, keyed by channel.
+    ///
+    /// Channels reporting NaN (not connected or error) are not contained in the map.
+    pub fn read_connected(&mut self) -> Result<BTreeMap<u8, f32>> {
+        Ok(self
+            .read_temperature()?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, temperature)| !temperature.is_nan())
+            .map(|(channel, temperature)| (channel as u8, temperature))
+            .collect())
+    }
+
+    /// Read the current temperature from all channels in °C and check them against a plausible range.
+    ///
+    /// Temperatures outside of `min..=max` are replaced by NaN, the validity per channel is returned
+    /// alongside. See [`crate::analysis::mark_implausible`].
+    pub fn read_temperature_plausible(
+        &mut self,
+        min: f32,
+        max: f32,
+    ) -> Result<(Vec<f32>, Vec<crate::analysis::ChannelValidity>)> {
+        let mut temperatures = self.read_temperature()?;
+        let validity = crate::analysis::mark_implausible(&mut temperatures, min, max);
+        Ok((temperatures, validity))
+    }
+
     /// Read the current temperature correction values form all channels in °C.
     pub fn read_temperature_correction(&mut self) -> Result<Vec<f32>> {
         let rsp = self.ctx.read_holding_registers(
-            proto::READ_TEMPERATURE_CORRECTION_REG_ADDR,
+            self.layout.temperature_correction_reg_addr,
             proto::READ_TEMPERATURE_CORRECTION_REG_QUAN,
         )??;
         Ok(rsp
             .iter()
-            .map(|value| proto::degree_celsius_decode(*value))
+            .map(|value| {
+                proto::degree_celsius_decode_with(
+                    self.byte_order.apply(*value),
+                    self.correction_sentinel,
+                )
+            })
             .collect::<Vec<_>>())
     }
 
@@ -59,23 +301,190 @@ impl R4DCB08 {
     /// Setting it to 0.0 disables this feature.
     ///
     /// * 'channel' - Temperature sensore channel 0 to 7.
-    /// * 'correction' - Correction value in °Celsius
+    /// * 'correction' - Correction value in °Celsius, see [`Self::set_strict_correction_range`].
     pub fn set_temperature_correction(&mut self, channel: u8, correction: f32) -> Result<()> {
         proto::write_temperature_correction_check_channel(channel)?;
         Ok(self.ctx.write_single_register(
-            proto::WRITE_TEMPERATURE_CORRECTION_REG_ADDR + channel as u16,
-            proto::degree_celsius_encode(correction)?,
+            self.layout.temperature_correction_reg_addr + channel as u16,
+            self.byte_order.apply(self.encode_correction(correction)?),
         )??)
     }
 
+    /// Sets the temperature correction of a channel so it reads the `reference` temperature, e.g. of a reference thermometer.
+    ///
+    /// The current temperature and correction are read first, see [`proto::calibration_correction`].
+    /// Returns the new correction value in °Celsius.
+    pub fn calibrate_channel_to(&mut self, channel: u8, reference: f32) -> Result<f32> {
+        proto::write_temperature_correction_check_channel(channel)?;
+        let reading = self.read_temperature()?[channel as usize];
+        if reading.is_nan() {
+            return Err(crate::Error::ChannelDisconnected(channel).into());
+        }
+        let correction = self.read_temperature_correction()?[channel as usize];
+        let correction = proto::calibration_correction(reading, correction, reference)?;
+        self.set_temperature_correction(channel, correction)?;
+        Ok(correction)
+    }
+
+    /// Set the temperature corrections of the channels `0..table.len()` from a calibration table
+    /// and read back the temperatures of all channels in °C.
+    ///
+    /// Each correction is `reference - measured`, see [`proto::calibration_table_corrections`].
+    /// All corrections are checked to be within ±10 °C before anything is written,
+    /// they are written with [`Self::write_corrections_and_read_temperatures`].
+    ///
+    /// * 'table' - `(reference, measured)` pairs in °Celsius per channel, at most 8. `measured` is the uncorrected reading.
+    pub fn apply_calibration_table(&mut self, table: &[(f32, f32)]) -> Result<Vec<f32>> {
+        let corrections = proto::calibration_table_corrections(table)?;
+        self.write_corrections_and_read_temperatures(&corrections)
+    }
+
+    /// Set the temperature correction values of the channels `0..corrections.len()` and read back
+    /// the temperatures of all channels in °C, e.g. to verify a calibration.
+    ///
+    /// Both is done in one Read/Write Multiple Registers (0x17) transaction. If the device rejects
+    /// this function code, the corrections are written one by one with rollback on failure like
+    /// [`Self::set_temperature_correction_all_atomic`] and the temperatures are read afterwards.
+    ///
+    /// * 'corrections' - Correction values in °Celsius, at most 8.
+    pub fn write_corrections_and_read_temperatures(
+        &mut self,
+        corrections: &[f32],
+    ) -> Result<Vec<f32>> {
+        proto::check_channel_count(corrections.len())?;
+        if corrections.is_empty() {
+            return self.read_temperature();
+        }
+        let encoded = corrections
+            .iter()
+            .map(|correction| {
+                self.encode_correction(*correction)
+                    .map(|value| self.byte_order.apply(value))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let rsp = self.ctx.read_write_multiple_registers(
+            self.layout.temperature_reg_addr,
+            proto::READ_TEMPERATURE_REG_QUAN,
+            self.layout.temperature_correction_reg_addr,
+            &encoded,
+        )?;
+        let rsp = match rsp {
+            Ok(rsp) => rsp,
+            Err(ExceptionCode::IllegalFunction) => {
+                self.set_temperature_correction_all_atomic(corrections)?;
+                self.ctx.read_holding_registers(
+                    self.layout.temperature_reg_addr,
+                    proto::READ_TEMPERATURE_REG_QUAN,
+                )??
+            }
+            Err(exception) => return Err(exception.into()),
+        };
+        Ok(rsp
+            .iter()
+            .map(|value| proto::degree_celsius_decode(self.byte_order.apply(*value)))
+            .collect())
+    }
+
+    /// Set the temperature correction values of the channels `0..corrections.len()` with rollback on failure.
+    ///
+    /// The values are written one by one with single register writes. The original values are read first,
+    /// if a write fails the already written channels are set back to their original values (best-effort).
+    /// The state of each channel is returned in [`crate::tokio_error::Error::CorrectionWriteFailed`].
+    ///
+    /// * 'corrections' - Correction values in °Celsius, at most 8.
+    pub fn set_temperature_correction_all_atomic(&mut self, corrections: &[f32]) -> Result<()> {
+        proto::check_channel_count(corrections.len())?;
+        let encoded = corrections
+            .iter()
+            .map(|correction| self.encode_correction(*correction))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        // The original values are written back as read, without decoding
+        let originals = self.ctx.read_holding_registers(
+            self.layout.temperature_correction_reg_addr,
+            corrections.len() as u16,
+        )??;
+        for (channel, value) in encoded.iter().enumerate() {
+            let written = self
+                .ctx
+                .write_single_register(
+                    self.layout.temperature_correction_reg_addr + channel as u16,
+                    self.byte_order.apply(*value),
+                )
+                .map_err(crate::tokio_error::Error::from)
+                .and_then(|rsp| rsp.map_err(crate::tokio_error::Error::from));
+            if let Err(error) = written {
+                let mut states = vec![proto::CorrectionWriteState::Unchanged; encoded.len()];
+                states[channel] = proto::CorrectionWriteState::Failed;
+                for (rollback, original) in originals.iter().enumerate().take(channel) {
+                    let rolled_back = matches!(
+                        self.ctx.write_single_register(
+                            self.layout.temperature_correction_reg_addr + rollback as u16,
+                            *original,
+                        ),
+                        Ok(Ok(()))
+                    );
+                    states[rollback] = if rolled_back {
+                        proto::CorrectionWriteState::RolledBack
+                    } else {
+                        proto::CorrectionWriteState::RollbackFailed
+                    };
+                }
+                return Err(crate::tokio_error::Error::CorrectionWriteFailed {
+                    error: Box::new(error),
+                    states,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the temperature correction value per channel, but only if a sensor is connected.
+    ///
+    /// The current temperature of the channel is read first. If the channel reports NaN,
+    /// no sensor is connected and [`crate::Error::ChannelDisconnected`] is returned.
+    /// Use [`Self::set_temperature_correction`] to configure channels before wiring the sensors.
+    ///
+    /// * 'channel' - Temperature sensore channel 0 to 7.
+    /// * 'correction' - Correction value in °Celsius
+    pub fn set_temperature_correction_if_connected(
+        &mut self,
+        channel: u8,
+        correction: f32,
+    ) -> Result<()> {
+        proto::write_temperature_correction_check_channel(channel)?;
+        let temperatures = self.read_temperature()?;
+        if temperatures
+            .get(channel as usize)
+            .is_none_or(|temperature| temperature.is_nan())
+        {
+            return Err(crate::Error::ChannelDisconnected(channel).into());
+        }
+        self.set_temperature_correction(channel, correction)
+    }
+
     /// Read temperature automatic reporting
     pub fn read_automatic_report(&mut self) -> Result<Duration> {
         let rsp = self.ctx.read_holding_registers(
-            proto::READ_AUTOMATIC_REPORT_REG_ADDR,
+            self.layout.automatic_report_reg_addr,
             proto::READ_AUTOMATIC_REPORT_REG_QUAN,
         )??;
         Ok(proto::read_automatic_report_decode_duration(
-            *rsp.first().expect("Result on success expected"),
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
+        ))
+    }
+
+    /// Read temperature automatic reporting with the upper byte of the register separately.
+    ///
+    /// See [`proto::read_automatic_report_decode_lenient`].
+    pub fn read_automatic_report_lenient(&mut self) -> Result<proto::AutomaticReport> {
+        let rsp = self.ctx.read_holding_registers(
+            self.layout.automatic_report_reg_addr,
+            proto::READ_AUTOMATIC_REPORT_REG_QUAN,
+        )??;
+        Ok(proto::read_automatic_report_decode_lenient(
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
         ))
     }
 
@@ -84,61 +493,273 @@ impl R4DCB08 {
     /// The value is set for all 8 channels at the same time.
     ///
     /// * 'report_in_sec' - Report time in seconds. 0 = disabled (default) or from 1 to 255 seconds.
+    ///
+    /// See [`Self::set_ignore_automatic_report_timeout`] for firmware not responding to this write.
     pub fn set_automatic_report(&mut self, report: Duration) -> Result<()> {
-        Ok(self.ctx.write_single_register(
-            proto::WRITE_AUTOMATIC_REPORT_REG_ADDR,
-            proto::write_automatic_report_encode_duration(report)?,
-        )??)
+        let rsp = self.ctx.write_single_register(
+            self.layout.automatic_report_reg_addr,
+            self.byte_order
+                .apply(proto::write_automatic_report_encode_duration(report)?),
+        );
+        match rsp {
+            Err(tokio_modbus::Error::Transport(error))
+                if self.ignore_automatic_report_timeout
+                    && error.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                Ok(())
+            }
+            rsp => Ok(rsp??),
+        }
     }
 
     /// Read the current baud rate
     pub fn read_baud_rate(&mut self) -> Result<proto::BaudRate> {
         let rsp = self.ctx.read_holding_registers(
-            proto::READ_BAUD_RATE_REG_ADDR,
+            self.layout.baud_rate_reg_addr,
             proto::READ_BAUD_RATE_REG_QUAN,
         )??;
-        Ok(proto::BaudRate::decode(
-            *rsp.first().expect("Result on success expected"),
-        ))
+        Ok(proto::BaudRate::try_decode(
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
+        )?)
     }
 
     /// Set the baud rate.
     ///
     /// Note: The baud rate will be updated when the module is powered up again!
     pub fn set_baud_rate(&mut self, baud_rate: proto::BaudRate) -> Result<()> {
-        Ok(self
-            .ctx
-            .write_single_register(proto::WRITE_BAUD_RATE_REG_ADDR, baud_rate.encode())??)
+        Ok(self.ctx.write_single_register(
+            self.layout.baud_rate_reg_addr,
+            self.byte_order.apply(baud_rate.encode()),
+        )??)
     }
 
     /// Reset the device to the factory default settings.
     pub fn factory_reset(&mut self) -> Result<()> {
         Ok(self.ctx.write_single_register(
-            proto::WRITE_FACTORY_RESET_REG_ADDR,
-            proto::WRITE_FACTORY_RESET_REG_DATA,
+            self.layout.factory_reset_reg_addr,
+            self.byte_order.apply(proto::WRITE_FACTORY_RESET_REG_DATA),
         )??)
     }
 
     /// Reads the current Modbus address
     ///
-    /// Note: When using this command, only one temperature module can be connected to the RS485 bus,
-    /// more than one will be wrong!
-    /// The connected modbus address must be the broadcast address 255.
+    /// The address register is read from the device the client is connected to, with a known address
+    /// this confirms the address of the device.
+    /// If the address is unknown, connect to the broadcast address 255
+    /// ([`proto::READ_ADDRESS_BROADCAST_ADDRESS`]). In that case only one temperature module can be connected
+    /// to the RS485 bus, more than one will be wrong!
+    /// A device reporting the broadcast address itself is misconfigured, see [`crate::Error::BroadcastAddressReported`].
     pub fn read_address(&mut self) -> Result<u8> {
         let rsp = self
             .ctx
-            .read_holding_registers(proto::READ_ADDRESS_REG_ADDR, proto::READ_ADDRESS_REG_QUAN)??;
-        Ok(*rsp.first().expect("Result on success expected") as u8)
+            .read_holding_registers(self.layout.address_reg_addr, proto::READ_ADDRESS_REG_QUAN)??;
+        Ok(proto::read_address_decode(
+            self.byte_order
+                .apply(*rsp.first().expect("Result on success expected")),
+        )?)
+    }
+
+    /// Returns true if a device responds at the `candidate` address, e.g. before assigning it with [`Self::set_address`].
+    ///
+    /// The address register is read from `candidate` with the timeout of the connection set to `timeout`,
+    /// any response including a Modbus exception means the address is in use, a timeout means it is free.
+    /// Afterwards the client talks to its own address with its previous timeout again. Fails without sending
+    /// anything if the own address is unknown, see [`Self::slave_address`].
+    pub fn is_address_in_use(&mut self, candidate: u8, timeout: Duration) -> Result<bool> {
+        proto::write_address_encode_address(candidate)?;
+        let address = self
+            .slave_address
+            .ok_or(crate::tokio_error::Error::SlaveAddressUnknown)?;
+        let previous_timeout = self.timeout();
+        self.ctx.set_slave(tokio_modbus::Slave(candidate));
+        self.ctx.set_timeout(timeout);
+        let rsp = self
+            .ctx
+            .read_holding_registers(self.layout.address_reg_addr, proto::READ_ADDRESS_REG_QUAN);
+        self.ctx.set_slave(tokio_modbus::Slave(address));
+        self.ctx.set_timeout(previous_timeout);
+        match rsp {
+            Ok(_) => Ok(true),
+            Err(tokio_modbus::Error::Transport(error))
+                if error.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error.into()),
+        }
     }
 
     /// Set the Modbus address
     ///
-    /// * 'address' - The address can be from 1 to 247.
+    /// * 'address' - The address can be from 1 to 247, the broadcast address 255 is rejected.
     pub fn set_address(&mut self, address: u8) -> Result<()> {
         self.ctx.write_single_register(
-            proto::WRITE_ADDRESS_REG_ADDR,
-            proto::write_address_encode_address(address)?,
+            self.layout.address_reg_addr,
+            self.byte_order
+                .apply(proto::write_address_encode_address(address)?),
         )??;
         Ok(())
     }
+
+    /// Reads the automatic report, address and baud rate at once.
+    ///
+    /// The registers are read with a single request if they are contiguous in the register layout,
+    /// which is the case for the R4DCB08. Otherwise each register is read on its own.
+    pub fn read_config_block(&mut self) -> Result<proto::ConfigBlock> {
+        let words = if self.layout.config_block_contiguous() {
+            self.ctx.read_holding_registers(
+                self.layout.automatic_report_reg_addr,
+                proto::READ_CONFIG_BLOCK_REG_QUAN,
+            )??
+        } else {
+            let mut words = Vec::with_capacity(proto::READ_CONFIG_BLOCK_REG_QUAN as usize);
+            for reg_addr in [
+                self.layout.automatic_report_reg_addr,
+                self.layout.address_reg_addr,
+                self.layout.baud_rate_reg_addr,
+            ] {
+                words.extend(self.ctx.read_holding_registers(reg_addr, 1)??);
+            }
+            words
+        };
+        let words = words
+            .iter()
+            .map(|value| self.byte_order.apply(*value))
+            .collect::<Vec<_>>();
+        Ok(proto::read_config_block_decode(&words)?)
+    }
+
+    /// Commissions the device by applying the configuration in the required order, see [`proto::DeviceConfig::steps`].
+    ///
+    /// The configuration is checked before anything is written. The client waits `delay` between the
+    /// requests and switches to the new address once it is written.
+    /// Note: The baud rate will be updated when the module is powered up again,
+    /// see [`proto::CommissionReport::power_cycle_required`].
+    pub fn commission(
+        &mut self,
+        config: &proto::DeviceConfig,
+        delay: Duration,
+    ) -> Result<proto::CommissionReport> {
+        config.check()?;
+        let mut report = proto::CommissionReport::default();
+        for step in config.steps() {
+            if !report.applied.is_empty() {
+                std::thread::sleep(delay);
+            }
+            match step {
+                proto::CommissionStep::TemperatureCorrection {
+                    channel,
+                    correction,
+                } => self.set_temperature_correction(channel, correction)?,
+                proto::CommissionStep::AutomaticReport(report) => {
+                    self.set_automatic_report(report)?
+                }
+                proto::CommissionStep::Address(address) => {
+                    self.set_address(address)?;
+                    self.set_slave_address(address);
+                }
+                proto::CommissionStep::BaudRate(baud_rate) => self.set_baud_rate(baud_rate)?,
+            }
+            report.applied.push(step);
+        }
+        Ok(report)
+    }
+
+    /// Reads the device identity using the Modbus function "Report Slave ID" (0x11).
+    ///
+    /// The response format of the R4DCB08 is not documented, the raw response bytes are returned
+    /// (usually the byte count followed by the slave ID, the run indicator status and device specific data).
+    pub fn report_slave_id(&mut self) -> Result<Vec<u8>> {
+        match self.ctx.call(Request::Custom(
+            proto::REPORT_SLAVE_ID_FUNCTION_CODE,
+            Cow::Borrowed(&[]),
+        ))?? {
+            Response::Custom(_, data) => Ok(data.to_vec()),
+            _ => Err(crate::Error::UnexpectedResponse(proto::REPORT_SLAVE_ID_FUNCTION_CODE).into()),
+        }
+    }
+
+    /// Reads the diagnostic counters using the Modbus diagnostics function (0x08).
+    ///
+    /// This is best-effort, the firmware of the R4DCB08 is not documented to support this function.
+    /// Sub-functions answered with an exception or an unexpected response are reported as `None`.
+    ///
+    /// Note: The RTU framing of tokio-modbus cannot determine the length of a successful diagnostics response,
+    /// therefore counters can only be read with Modbus/TCP (e.g. a RS485 to TCP gateway).
+    pub fn read_diagnostics(&mut self) -> Result<proto::Diagnostics> {
+        Ok(proto::Diagnostics {
+            bus_message_count: self
+                .read_diagnostics_counter(proto::DIAGNOSTICS_RETURN_BUS_MESSAGE_COUNT)?,
+            bus_communication_error_count: self.read_diagnostics_counter(
+                proto::DIAGNOSTICS_RETURN_BUS_COMMUNICATION_ERROR_COUNT,
+            )?,
+        })
+    }
+
+    fn read_diagnostics_counter(&mut self, sub_function: u16) -> Result<Option<u16>> {
+        let request = proto::diagnostics_encode_request(sub_function);
+        match self.ctx.call(Request::Custom(
+            proto::DIAGNOSTICS_FUNCTION_CODE,
+            Cow::Borrowed(&request),
+        ))? {
+            Ok(Response::Custom(_, data)) => {
+                Ok(proto::diagnostics_decode_response(sub_function, &data))
+            }
+            Ok(_) => Ok(None),
+            Err(ExceptionCode::IllegalFunction | ExceptionCode::IllegalDataValue) => Ok(None),
+            Err(exception) => Err(exception.into()),
+        }
+    }
+}
+
+/// Temperatures of several devices on one bus by RS485 address, see [`R4DCB08::read_devices`].
+#[derive(Debug, Default)]
+pub struct MultiDeviceTemperatures(pub BTreeMap<u8, Result<Vec<f32>>>);
+
+impl fmt::Display for MultiDeviceTemperatures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (address, temperatures) in &self.0 {
+            match temperatures {
+                Ok(temperatures) => writeln!(f, "{address:#04x}: {temperatures:?}")?,
+                Err(error) => writeln!(f, "{address:#04x}: {error}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl R4DCB08 {
+    /// Reads the temperatures of the devices at `addresses` sharing the bus, one after another separated by `delay`.
+    ///
+    /// A failing device does not stop reading the others.
+    /// Afterwards the client talks to the last address, see [`Self::set_slave_address`].
+    pub fn read_devices(&mut self, addresses: &[u8], delay: Duration) -> MultiDeviceTemperatures {
+        let mut devices = MultiDeviceTemperatures::default();
+        for (index, address) in addresses.iter().enumerate() {
+            if index > 0 {
+                std::thread::sleep(delay);
+            }
+            self.set_slave_address(*address);
+            devices.0.insert(*address, self.read_temperature());
+        }
+        devices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_device_temperatures_display() {
+        let devices = MultiDeviceTemperatures(BTreeMap::from([
+            (0x02, Err(crate::Error::ChannelDisconnected(0).into())),
+            (0x01, Ok(vec![21.9, f32::NAN])),
+        ]));
+        assert_eq!(
+            devices.to_string(),
+            "0x01: [21.9, NaN]\n0x02: R4DCB08 error: The channel 0 reports no connected sensor\n"
+        );
+    }
 }